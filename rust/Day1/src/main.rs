@@ -1,18 +1,29 @@
 std::include!("../../helpers.rs");
 
-// const TEST_INPUT1: &[usize] = &[1721, 979, 366, 299, 675, 1456];
+const TARGET: usize = 2020;
 
 fn main() {
-    let ((), time_total) = time(||
-    {
-        let (stdin, time_reading) = time(|| read_stdin());
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        match parse_args(&args) {
+            Some((k, target)) => run_k_sum(k, target),
+            None => {
+                eprintln!("usage: day1 [--k <count>] [--target <sum>]");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let ((), time_total) = time(|| {
+        let (stdin, time_reading) = time(read_stdin);
         let (lines, time_parsing) = time(|| read_challenge_input(stdin));
-        
+
         let (solution_1, time_solving_1) = time(|| solve_challenge_1(&lines[..]));
         let (solution_2, time_solving_2) = time(|| solve_challenge_2(&lines[..]));
-        
-        println!("solution 1: {}", solution_1);
-        println!("solution 2: {}", solution_2);
+
+        println!("solution 1: {}", format_solution(solution_1));
+        println!("solution 2: {}", format_solution(solution_2));
         println!("took {:?} to read input", time_reading);
         println!("took {:?} to parse input", time_parsing);
         println!("took {:?} to solve", time_solving_1);
@@ -22,34 +33,225 @@ fn main() {
     println!("took {:?} in total", time_total);
 }
 
+/// Parses `--k <count>` and `--target <sum>`, which both default to the values of part 2. `k`
+/// has to be at least 1.
+fn parse_args(args: &[String]) -> Option<(usize, usize)> {
+    let mut k = 3;
+    let mut target = TARGET;
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let value = iter.next()?.parse().ok()?;
+        match flag.as_str() {
+            "--k" if value > 0 => k = value,
+            "--target" => target = value,
+            _ => return None,
+        }
+    }
+    Some((k, target))
+}
+
+fn run_k_sum(k: usize, target: usize) {
+    let values = read_challenge_input(read_stdin());
+    let (tuples, time_solving) = time(|| find_k_sum(&values, k, target));
+
+    match tuples {
+        Some(tuples) => {
+            for indices in &tuples {
+                let operands = indices.iter().map(|&i| values[i]).collect::<Vec<_>>();
+                let sum = operands
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" + ");
+                let product = operands.iter().product::<usize>();
+                println!(
+                    "{} = {}, product {} (indices {:?})",
+                    sum, target, product, indices
+                );
+            }
+            println!("found {} combinations", tuples.len());
+        }
+        None => println!("no {} values sum to {}", k, target),
+    }
+    println!("took {:?} to solve", time_solving);
+}
+
+fn format_solution(solution: Option<usize>) -> String {
+    solution.map_or_else(|| "no solution".to_owned(), |s| s.to_string())
+}
+
 fn read_challenge_input(input: String) -> Vec<usize> {
     input.lines().map(|line| line.parse().unwrap()).collect()
 }
 
-fn solve_challenge_1(input: &[usize]) -> usize {
-    for i in 0..input.len() {
-        let operand1 = input[i];
-        let mut iter = input.iter().skip(i).filter(|i| *i + operand1 == 2020);
-        if let Some(operand2) = iter.next() {
-            return operand1 * *operand2;
-        }
+fn solve_challenge_1(input: &[usize]) -> Option<usize> {
+    product_of_first(input, find_k_sum(input, 2, TARGET)?)
+}
+
+fn solve_challenge_2(input: &[usize]) -> Option<usize> {
+    product_of_first(input, find_k_sum(input, 3, TARGET)?)
+}
+
+fn product_of_first(input: &[usize], tuples: Vec<Vec<usize>>) -> Option<usize> {
+    Some(tuples.first()?.iter().map(|&i| input[i]).product())
+}
+
+/// Finds every set of `k` different entries in `values` that add up to `target`, as sorted lists
+/// of indices. Returns `None` if there are none, which is always the case for a `k` of 0.
+///
+/// Sorts the values and then fixes all but the last two entries, finding the last two with two
+/// pointers, so this takes `O(n^(k-1))` time.
+fn find_k_sum(values: &[usize], k: usize, target: usize) -> Option<Vec<Vec<usize>>> {
+    if k == 0 {
+        return None;
+    }
+
+    let mut order = (0..values.len()).collect::<Vec<_>>();
+    order.sort_unstable_by_key(|&i| values[i]);
+    let sorted = order.iter().map(|&i| values[i]).collect::<Vec<_>>();
+
+    let mut found = vec![];
+    k_sum(&sorted, 0, k, target, &mut vec![], &mut found);
+
+    if found.is_empty() {
+        return None;
     }
 
-    unreachable!()
+    let mut tuples = found
+        .into_iter()
+        .map(|positions| {
+            let mut indices = positions.into_iter().map(|p| order[p]).collect::<Vec<_>>();
+            indices.sort_unstable();
+            indices
+        })
+        .collect::<Vec<_>>();
+    tuples.sort_unstable();
+    Some(tuples)
+}
+
+/// Adds every combination of `k` positions from `start` onwards in `sorted` that sums to
+/// `target` to `found`, each prefixed with `chosen`. `k` has to be at least 1.
+fn k_sum(
+    sorted: &[usize],
+    start: usize,
+    k: usize,
+    target: usize,
+    chosen: &mut Vec<usize>,
+    found: &mut Vec<Vec<usize>>,
+) {
+    match k {
+        1 => {
+            for (p, &value) in sorted.iter().enumerate().skip(start) {
+                if value == target {
+                    found.push([&chosen[..], &[p]].concat());
+                }
+            }
+        }
+        2 => two_sum(sorted, start, target, chosen, found),
+        _ => {
+            for p in start..sorted.len() {
+                // everything after this is at least as big, so nothing else can fit
+                if sorted[p].saturating_mul(k) > target {
+                    break;
+                }
+                chosen.push(p);
+                k_sum(sorted, p + 1, k - 1, target - sorted[p], chosen, found);
+                chosen.pop();
+            }
+        }
+    }
 }
 
-fn solve_challenge_2(input: &[usize]) -> usize {
-    for i in 0..input.len() {
-        let operand1 = input[i];
-        let iter = input.iter().enumerate().skip(i).filter(|(_, i)| *i + operand1 <= 2020);
-        
-        for (idx, operand2) in iter {
-            let mut iter2 = input.iter().skip(idx).filter(|i| *i + operand1 + operand2 == 2020);
-            if let Some(operand3) = iter2.next() {
-                return operand1 * operand2 * *operand3;
+fn two_sum(
+    sorted: &[usize],
+    start: usize,
+    target: usize,
+    chosen: &[usize],
+    found: &mut Vec<Vec<usize>>,
+) {
+    if sorted.len() < start + 2 {
+        return;
+    }
+
+    let (mut low, mut high) = (start, sorted.len() - 1);
+    while low < high {
+        let sum = sorted[low] + sorted[high];
+        if sum < target {
+            low += 1;
+        } else if sum > target {
+            high -= 1;
+        } else if sorted[low] == sorted[high] {
+            // every pair within this run of equal values matches
+            for a in low..high {
+                for b in a + 1..=high {
+                    found.push([chosen, &[a, b]].concat());
+                }
+            }
+            return;
+        } else {
+            // the runs of equal values at both ends, which all pair up with each other
+            let low_end = (low..=high).find(|&p| sorted[p] != sorted[low]).unwrap();
+            let high_start = (low_end..=high)
+                .rev()
+                .find(|&p| sorted[p] != sorted[high])
+                .map_or(low_end, |p| p + 1);
+            for a in low..low_end {
+                for b in high_start..=high {
+                    found.push([chosen, &[a, b]].concat());
+                }
             }
+            low = low_end;
+            high = high_start - 1;
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    const TEST_INPUT: &[usize] = &[1721, 979, 366, 299, 675, 1456];
+
+    #[test]
+    fn test_solutions() {
+        assert_eq!(Some(514579), solve_challenge_1(TEST_INPUT));
+        assert_eq!(Some(241861950), solve_challenge_2(TEST_INPUT));
+        assert_eq!(None, solve_challenge_1(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = |args: &[&str]| args.iter().map(|&a| a.to_owned()).collect::<Vec<_>>();
+        assert_eq!(Some((3, TARGET)), parse_args(&[]));
+        assert_eq!(
+            Some((4, 100)),
+            parse_args(&args(&["--k", "4", "--target", "100"]))
+        );
+        assert_eq!(None, parse_args(&args(&["--k", "0"])));
+        assert_eq!(None, parse_args(&args(&["--k"])));
+        assert_eq!(None, parse_args(&args(&["--n", "2"])));
+    }
+
+    #[test]
+    fn test_find_k_sum() {
+        assert_eq!(Some(vec![vec![0, 3]]), find_k_sum(TEST_INPUT, 2, 2020));
+        assert_eq!(Some(vec![vec![1, 2, 4]]), find_k_sum(TEST_INPUT, 3, 2020));
+        assert_eq!(None, find_k_sum(TEST_INPUT, 4, 2020));
+        assert_eq!(None, find_k_sum(TEST_INPUT, 0, 0));
 
-    unreachable!()
+        // 1010 can't be used twice, but every other pair with 1010 can
+        assert_eq!(None, find_k_sum(&[1010, 5], 2, 2020));
+        assert_eq!(
+            Some(vec![vec![0, 1], vec![0, 2], vec![1, 2]]),
+            find_k_sum(&[1010, 1010, 1010], 2, 2020)
+        );
+        assert_eq!(
+            Some(vec![vec![0, 1], vec![0, 3], vec![1, 2], vec![2, 3]]),
+            find_k_sum(&[1, 4, 1, 4, 3], 2, 5)
+        );
+        assert_eq!(
+            Some(vec![vec![0, 1, 2, 3], vec![0, 1, 3, 4]]),
+            find_k_sum(&[1, 2, 3, 4, 3], 4, 10)
+        );
+    }
 }
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use crate::rules::Rule;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Failure {
+    /// 1-based.
+    pub line: usize,
+    pub rules: Vec<String>,
+}
+
+/// The result of checking a list of passwords, one per line, against a rule.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Audit {
+    pub checked: usize,
+    pub failures: Vec<Failure>,
+}
+
+impl Audit {
+    /// Checks every non-empty line of `passwords`. A trailing `\r` is not part of the password.
+    pub fn run(rule: &dyn Rule, passwords: &str) -> Self {
+        let mut audit = Audit::default();
+        for (idx, password) in passwords.lines().enumerate() {
+            if password.is_empty() {
+                continue;
+            }
+
+            audit.checked += 1;
+            let rules = rule.failures(password);
+            if !rules.is_empty() {
+                audit.failures.push(Failure {
+                    line: idx + 1,
+                    rules,
+                });
+            }
+        }
+        audit
+    }
+
+    /// Lists the failed rules for each line, followed by how often each rule failed. The
+    /// passwords themselves are left out so the report can be shared.
+    pub fn report(&self) -> String {
+        let mut string = String::new();
+        let mut counts = HashMap::<&str, usize>::new();
+        for failure in &self.failures {
+            string += &format!("line {}: {}\n", failure.line, failure.rules.join(", "));
+            for rule in &failure.rules {
+                *counts.entry(rule).or_default() += 1;
+            }
+        }
+
+        string += &format!(
+            "{} of {} passwords failed\n",
+            self.failures.len(),
+            self.checked
+        );
+        let mut counts = counts.into_iter().collect::<Vec<_>>();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        for (rule, count) in counts {
+            string += &format!("{:>6}  {}\n", count, rule);
+        }
+        string
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::parse_rule;
+
+    #[test]
+    fn test_audit() {
+        let rule = parse_rule("and(distinct(3), forbid(\" \"))").unwrap();
+        let audit = Audit::run(&*rule, "abc\naa\n\nhunter 2\r\nab\n");
+
+        assert_eq!(4, audit.checked);
+        assert_eq!(
+            vec![
+                Failure {
+                    line: 2,
+                    rules: vec!["distinct(3)".to_owned()]
+                },
+                Failure {
+                    line: 4,
+                    rules: vec!["forbid(\" \")".to_owned()]
+                },
+                Failure {
+                    line: 5,
+                    rules: vec!["distinct(3)".to_owned()]
+                },
+            ],
+            audit.failures
+        );
+        assert_eq!(
+            "line 2: distinct(3)\nline 4: forbid(\" \")\nline 5: distinct(3)\n\
+            3 of 4 passwords failed\n     2  distinct(3)\n     1  forbid(\" \")\n",
+            audit.report()
+        );
+    }
+}
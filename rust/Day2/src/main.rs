@@ -1,3 +1,8 @@
+mod audit;
+mod rules;
+
+use rules::{CountInRange, ExactlyOnePosition, Rule};
+
 std::include!("../../helpers.rs");
 
 #[derive(Debug)]
@@ -6,33 +11,60 @@ struct PolicyWithPassword {
     pub password: String,
 }
 
+/// The two readings of a policy line, built once when parsing.
 #[derive(Debug)]
 struct Policy {
-    pub min: usize,
-    pub max: usize,
-    pub chr: u8,
+    pub count: CountInRange,
+    pub positions: ExactlyOnePosition,
 }
 
 impl PolicyWithPassword {
     pub fn check_1(&self) -> bool {
-        let iter = self.password.bytes().filter(|c| *c == self.policy.chr);
-        let count = iter.take(self.policy.max + 1).count();
-        count >= self.policy.min && count <= self.policy.max
+        self.policy.count.check(&self.password)
     }
 
     pub fn check_2(&self) -> bool {
-        let min = self.password.bytes().nth(self.policy.min - 1).unwrap();
-        let max = self.password.bytes().nth(self.policy.max - 1).unwrap();
-        (min == self.policy.chr) ^ (max == self.policy.chr)
+        self.policy.positions.check(&self.password)
     }
 }
 
 fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.split_first() {
+        None => run_challenge(),
+        Some((command, rules)) if command == "audit" && !rules.is_empty() => run_audit(rules),
+        _ => {
+            eprintln!("usage: day2 [audit <rule>...]");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Checks the passwords on stdin, one per line, against all the given rules.
+fn run_audit(rules: &[String]) {
+    let rules = match rules.iter().map(|r| rules::parse_rule(r)).collect() {
+        Ok(rules) => rules::And(rules),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let audit = audit::Audit::run(&rules, &read_stdin());
+    print!("{}", audit.report());
+    if !audit.failures.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+fn run_challenge() {
     let (_, time_total) = time(|| {
-        let (stdin, time_reading) = time(|| read_stdin());
+        let (stdin, time_reading) = time(read_stdin);
         let (passwords, time_parsing) = time(|| read_input(&stdin));
-        let (count_correct_1, time_solving_1) = time(|| passwords.iter().filter(|p| p.check_1()).count());
-        let (count_correct_2, time_solving_2) = time(|| passwords.iter().filter(|p| p.check_2()).count());
+        let (count_correct_1, time_solving_1) =
+            time(|| passwords.iter().filter(|p| p.check_1()).count());
+        let (count_correct_2, time_solving_2) =
+            time(|| passwords.iter().filter(|p| p.check_2()).count());
 
         println!("solution 1: {}", count_correct_1);
         println!("solution 2: {}", count_correct_2);
@@ -46,7 +78,11 @@ fn main() {
 }
 
 fn read_input(input: &str) -> Vec<PolicyWithPassword> {
-    input.split('\n').filter(|l| l != &"").map(parse_line).collect()
+    input
+        .split('\n')
+        .filter(|l| !l.is_empty())
+        .map(parse_line)
+        .collect()
 }
 
 fn parse_line(line: &str) -> PolicyWithPassword {
@@ -59,14 +95,23 @@ fn parse_line(line: &str) -> PolicyWithPassword {
     let max = split1.next().unwrap().parse().unwrap();
 
     let split2 = split.next().unwrap();
-    let chr = split2.bytes().next().unwrap();
+    let pattern = split2.trim_end_matches(':');
 
     let split3 = split.next().unwrap();
     let password = split3;
 
     PolicyWithPassword {
         policy: Policy {
-            min, max, chr,
+            count: CountInRange {
+                pattern: pattern.to_owned(),
+                min,
+                max,
+            },
+            positions: ExactlyOnePosition {
+                pattern: pattern.to_owned(),
+                first: min,
+                second: max,
+            },
         },
         password: password.into(),
     }
@@ -108,4 +153,14 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_patterns() {
+        let parsed = read_input("1-2 ab: abab\n2-4 é: aébé\n1-20 a: abc");
+        assert!(parsed[0].check_1() && parsed[0].check_2());
+        assert_eq!("é", parsed[1].policy.count.pattern);
+        assert!(parsed[1].check_1() && !parsed[1].check_2());
+        // position 20 doesn't exist, which fails instead of panicking
+        assert!(!parsed[2].check_2());
+    }
 }
@@ -0,0 +1,443 @@
+use std::{collections::HashSet, fmt};
+
+use regex::Regex;
+
+/// A single check that a password has to pass. Password policies are built by combining these,
+/// and they display as the same syntax that [`parse_rule`] reads.
+pub trait Rule: fmt::Display {
+    fn check(&self, password: &str) -> bool;
+
+    /// The rules that rejected `password`, going as deep into combined rules as possible.
+    fn failures(&self, password: &str) -> Vec<String> {
+        if self.check(password) {
+            vec![]
+        } else {
+            vec![self.to_string()]
+        }
+    }
+}
+
+/// `pattern` must occur between `min` and `max` times (inclusive). Occurrences can't overlap, so
+/// `aa` occurs once in `aaa`.
+#[derive(Debug)]
+pub struct CountInRange {
+    pub pattern: String,
+    pub min: usize,
+    pub max: usize,
+}
+
+impl Rule for CountInRange {
+    fn check(&self, password: &str) -> bool {
+        let iter = password.matches(self.pattern.as_str());
+        let count = iter.take(self.max + 1).count();
+        count >= self.min && count <= self.max
+    }
+}
+
+impl fmt::Display for CountInRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pattern = quote(&self.pattern);
+        write!(f, "count({}, {}, {})", pattern, self.min, self.max)
+    }
+}
+
+/// `pattern` must start at exactly one of the two positions. Positions count `char`s starting at
+/// 1, and the rule fails if either of them is not in the password.
+#[derive(Debug)]
+pub struct ExactlyOnePosition {
+    pub pattern: String,
+    pub first: usize,
+    pub second: usize,
+}
+
+impl ExactlyOnePosition {
+    /// Whether `pattern` starts at `position`, or `None` if there is no such position.
+    fn starts_at(&self, password: &str, position: usize) -> Option<bool> {
+        let (offset, _) = password.char_indices().nth(position.checked_sub(1)?)?;
+        Some(password[offset..].starts_with(self.pattern.as_str()))
+    }
+}
+
+impl Rule for ExactlyOnePosition {
+    fn check(&self, password: &str) -> bool {
+        match (
+            self.starts_at(password, self.first),
+            self.starts_at(password, self.second),
+        ) {
+            (Some(first), Some(second)) => first ^ second,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for ExactlyOnePosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pattern = quote(&self.pattern);
+        write!(f, "positions({}, {}, {})", pattern, self.first, self.second)
+    }
+}
+
+/// None of these characters may occur.
+pub struct ForbiddenChars(pub String);
+
+impl Rule for ForbiddenChars {
+    fn check(&self, password: &str) -> bool {
+        !password.chars().any(|c| self.0.contains(c))
+    }
+}
+
+impl fmt::Display for ForbiddenChars {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "forbid({})", quote(&self.0))
+    }
+}
+
+/// At least this many different characters must be used.
+pub struct MinDistinct(pub usize);
+
+impl Rule for MinDistinct {
+    fn check(&self, password: &str) -> bool {
+        password.chars().collect::<HashSet<_>>().len() >= self.0
+    }
+}
+
+impl fmt::Display for MinDistinct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "distinct({})", self.0)
+    }
+}
+
+/// The regex must match somewhere in the password. Anchor it to match the whole password.
+pub struct Matches(pub Regex);
+
+impl Rule for Matches {
+    fn check(&self, password: &str) -> bool {
+        self.0.is_match(password)
+    }
+}
+
+impl fmt::Display for Matches {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "regex({})", quote(self.0.as_str()))
+    }
+}
+
+pub struct And(pub Vec<Box<dyn Rule>>);
+
+impl Rule for And {
+    fn check(&self, password: &str) -> bool {
+        self.0.iter().all(|r| r.check(password))
+    }
+
+    fn failures(&self, password: &str) -> Vec<String> {
+        self.0.iter().flat_map(|r| r.failures(password)).collect()
+    }
+}
+
+impl fmt::Display for And {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "and({})", join(&self.0))
+    }
+}
+
+pub struct Or(pub Vec<Box<dyn Rule>>);
+
+impl Rule for Or {
+    fn check(&self, password: &str) -> bool {
+        self.0.iter().any(|r| r.check(password))
+    }
+}
+
+impl fmt::Display for Or {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "or({})", join(&self.0))
+    }
+}
+
+pub struct Not(pub Box<dyn Rule>);
+
+impl Rule for Not {
+    fn check(&self, password: &str) -> bool {
+        !self.0.check(password)
+    }
+}
+
+impl fmt::Display for Not {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "not({})", self.0)
+    }
+}
+
+fn join(rules: &[Box<dyn Rule>]) -> String {
+    rules
+        .iter()
+        .map(|r| r.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Quotes an argument if it would not be read back as a single plain one.
+fn quote(text: &str) -> String {
+    let plain = !text.is_empty()
+        && text
+            .chars()
+            .all(|c| !c.is_whitespace() && !matches!(c, ',' | '(' | ')' | '"' | '\\'));
+    if plain {
+        return text.to_owned();
+    }
+
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RuleParseError(pub String);
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid rule: {}", self.0)
+    }
+}
+
+enum Arg {
+    Rule(Box<dyn Rule>),
+    Text(String),
+}
+
+/// Parses a rule such as `and(count(a, 1, 3), not(regex("^[0-9]+$")))`.
+///
+/// The built-in rules are `count(pattern, min, max)`, `positions(pattern, first, second)`,
+/// `forbid(chars)`, `distinct(n)`, `regex(pattern)`, `and(rules...)`, `or(rules...)` and
+/// `not(rule)`. Arguments containing whitespace, commas, parentheses or quotes must be quoted,
+/// using `\` to escape `"` and `\`.
+pub fn parse_rule(input: &str) -> Result<Box<dyn Rule>, RuleParseError> {
+    let mut rest = input;
+    let rule = parse_call(&mut rest)?;
+    if !rest.trim().is_empty() {
+        return Err(RuleParseError(format!("unexpected '{}'", rest.trim())));
+    }
+    Ok(rule)
+}
+
+fn parse_call(rest: &mut &str) -> Result<Box<dyn Rule>, RuleParseError> {
+    *rest = rest.trim_start();
+    let name_len = rest
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(rest.len());
+    let name = &rest[..name_len];
+    *rest = rest[name_len..].trim_start();
+    *rest = rest
+        .strip_prefix('(')
+        .ok_or_else(|| RuleParseError(format!("expected '(' after '{}'", name)))?;
+
+    let mut args = vec![];
+    loop {
+        *rest = rest.trim_start();
+        if let Some(r) = rest.strip_prefix(')') {
+            *rest = r;
+            break;
+        }
+        if !args.is_empty() {
+            *rest = rest
+                .strip_prefix(',')
+                .ok_or_else(|| RuleParseError(format!("expected ',' or ')' in {}()", name)))?
+                .trim_start();
+        }
+        args.push(parse_arg(rest)?);
+    }
+
+    build_rule(name, args)
+}
+
+fn parse_arg(rest: &mut &str) -> Result<Arg, RuleParseError> {
+    if let Some(r) = rest.strip_prefix('"') {
+        let mut text = String::new();
+        let mut chars = r.char_indices();
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '"' => {
+                    *rest = &r[idx + 1..];
+                    return Ok(Arg::Text(text));
+                }
+                '\\' => text.extend(chars.next().map(|(_, c)| c)),
+                c => text.push(c),
+            }
+        }
+        return Err(RuleParseError("unterminated string".to_owned()));
+    }
+
+    let len = rest.find([',', '(', ')']).unwrap_or(rest.len());
+    if rest[len..].starts_with('(') {
+        return parse_call(rest).map(Arg::Rule);
+    }
+
+    let text = rest[..len].trim_end().to_owned();
+    *rest = &rest[len..];
+    Ok(Arg::Text(text))
+}
+
+fn build_rule(name: &str, args: Vec<Arg>) -> Result<Box<dyn Rule>, RuleParseError> {
+    let error = |expected: &str| RuleParseError(format!("expected {}({})", name, expected));
+
+    let mut texts = vec![];
+    let mut rules = vec![];
+    for arg in args {
+        match arg {
+            Arg::Text(t) => texts.push(t),
+            Arg::Rule(r) => rules.push(r),
+        }
+    }
+    let number = |idx: usize| texts[idx].parse::<usize>().ok();
+    let pattern = |idx: usize| Some(&texts[idx]).filter(|t| !t.is_empty()).cloned();
+
+    Ok(match (name, texts.len(), rules.len()) {
+        ("count", 3, 0) => {
+            let usage = || error("pattern, min, max");
+            Box::new(CountInRange {
+                pattern: pattern(0).ok_or_else(usage)?,
+                min: number(1).ok_or_else(usage)?,
+                max: number(2).ok_or_else(usage)?,
+            })
+        }
+        ("positions", 3, 0) => {
+            let usage = || error("pattern, first, second");
+            Box::new(ExactlyOnePosition {
+                pattern: pattern(0).ok_or_else(usage)?,
+                first: number(1).ok_or_else(usage)?,
+                second: number(2).ok_or_else(usage)?,
+            })
+        }
+        ("forbid", 1, 0) => Box::new(ForbiddenChars(texts.remove(0))),
+        ("distinct", 1, 0) => Box::new(MinDistinct(number(0).ok_or_else(|| error("n"))?)),
+        ("regex", 1, 0) => Box::new(Matches(
+            Regex::new(&texts[0]).map_err(|e| RuleParseError(e.to_string()))?,
+        )),
+        ("and", 0, n) if n > 0 => Box::new(And(rules)),
+        ("or", 0, n) if n > 0 => Box::new(Or(rules)),
+        ("not", 0, 1) => Box::new(Not(rules.remove(0))),
+        ("count", ..) => return Err(error("pattern, min, max")),
+        ("positions", ..) => return Err(error("pattern, first, second")),
+        ("forbid", ..) => return Err(error("chars")),
+        ("distinct", ..) => return Err(error("n")),
+        ("regex", ..) => return Err(error("pattern")),
+        ("and", ..) | ("or", ..) => return Err(error("rules...")),
+        ("not", ..) => return Err(error("rule")),
+        _ => return Err(RuleParseError(format!("unknown rule '{}'", name))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        let rule = parse_rule("count(a, 1, 3)").unwrap();
+        assert!(rule.check("abcde"));
+        assert!(!rule.check("cdefg"));
+
+        let rule = parse_rule("forbid(xyz)").unwrap();
+        assert!(rule.check("abc"));
+        assert!(!rule.check("abcz"));
+
+        let rule = parse_rule("distinct(3)").unwrap();
+        assert!(rule.check("abca"));
+        assert!(!rule.check("abab"));
+
+        let rule = parse_rule("regex(\"^[a-z]+$\")").unwrap();
+        assert!(rule.check("abc"));
+        assert!(!rule.check("abc1"));
+    }
+
+    #[test]
+    fn test_patterns() {
+        let rule = parse_rule("count(ab, 2, 2)").unwrap();
+        assert!(rule.check("abxab"));
+        assert!(!rule.check("ab"));
+        assert!(!parse_rule("count(aa, 2, 2)").unwrap().check("aaa"));
+
+        let rule = parse_rule("positions(ab, 1, 3)").unwrap();
+        assert!(rule.check("abcde"));
+        assert!(!rule.check("ababe"));
+    }
+
+    #[test]
+    fn test_unicode() {
+        let rule = parse_rule("positions(é, 2, 3)").unwrap();
+        assert!(rule.check("aéb"));
+        assert!(rule.check("🦀🦀é"));
+        assert!(!rule.check("aéé"));
+
+        let rule = parse_rule("count(ß, 1, 1)").unwrap();
+        assert!(rule.check("straße"));
+        assert!(!rule.check("strasse"));
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let rule = parse_rule("positions(a, 1, 4)").unwrap();
+        assert!(!rule.check("abc"));
+        assert!(!rule.check(""));
+        assert!(!rule.check("abca"));
+        assert!(rule.check("abcd"));
+
+        let rule = parse_rule("positions(a, 0, 1)").unwrap();
+        assert!(!rule.check("abc"));
+    }
+
+    #[test]
+    fn test_combinations() {
+        let rule = parse_rule(
+            "and(distinct(4), not(regex(\"^[0-9]*$\")), or(forbid(\"!?\"), count(!, 2, 2)))",
+        )
+        .unwrap();
+        assert!(rule.check("abcd"));
+        assert!(rule.check("a!b!c"));
+        assert!(!rule.check("123456"));
+        assert!(!rule.check("ab!c"));
+
+        assert_eq!(
+            vec!["distinct(4)".to_owned(), "not(regex(^[0-9]*$))".to_owned()],
+            rule.failures("111")
+        );
+        assert_eq!(
+            vec!["or(forbid(!?), count(!, 2, 2))".to_owned()],
+            rule.failures("ab!c")
+        );
+        assert!(rule.failures("abcd").is_empty());
+    }
+
+    #[test]
+    fn test_display_roundtrip() {
+        let input = "or(positions(a, 1, 3), not(forbid(\"a \\\"b\\\\\")))";
+        let rule = parse_rule(input).unwrap();
+        assert_eq!(input, rule.to_string());
+        assert_eq!(input, parse_rule(&rule.to_string()).unwrap().to_string());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            RuleParseError("unknown rule 'length'".to_owned()),
+            parse_rule("length(8)").err().unwrap()
+        );
+        assert_eq!(
+            RuleParseError("expected count(pattern, min, max)".to_owned()),
+            parse_rule("count(ab, x, 3)").err().unwrap()
+        );
+        assert!(parse_rule("count(\"\", 1, 3)").is_err());
+        assert_eq!(
+            RuleParseError("unexpected 'x'".to_owned()),
+            parse_rule("distinct(1) x").err().unwrap()
+        );
+        assert!(parse_rule("regex(\"(\")").is_err());
+        assert!(parse_rule("not(forbid(a)").is_err());
+    }
+}
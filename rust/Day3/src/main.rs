@@ -1,67 +1,248 @@
+mod render;
+
+use std::{fmt, ops::RangeInclusive};
+
+use render::Style;
+
 std::include!("../../helpers.rs");
 
 #[derive(Debug)]
-struct Map(Vec<Vec<bool>>);
+struct Map {
+    /// One bit per square, set for trees, with the leftmost square in the lowest bit.
+    rows: Vec<u64>,
+    width: usize,
+}
 
-impl Map {
-    #[cfg(test)]
-    pub fn to_string(&self) -> String {
-        self.0
-            .iter()
-            .map(|x| {
-                x.iter()
-                    .map(|y| if *y { '#' } else { '.' })
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = (0..self.rows.len())
+            .map(|row| {
+                (0..self.width)
+                    .map(|column| if self.is_tree(row, column) { '#' } else { '.' })
                     .collect::<String>()
             })
-            .collect::<Vec<String>>()
-            .join("\n")
+            .collect::<Vec<String>>();
+        write!(f, "{}", rows.join("\n"))
+    }
+}
+
+impl Map {
+    /// The column wraps around, since the map repeats to the right.
+    pub fn is_tree(&self, row: usize, column: usize) -> bool {
+        self.rows[row] >> (column % self.width) & 1 != 0
     }
 
     pub fn solve_1(&self) -> usize {
-        self.calculate_for_idx(3)
+        self.trees_on_slope(3, 1).unwrap()
     }
 
     pub fn solve_2(&self) -> usize {
-        self.calculate_for_idx(1)
-            * self.calculate_for_idx(3)
-            * self.calculate_for_idx(5)
-            * self.calculate_for_idx(7)
-            * self.calculate_for_double_idx(1)
+        self.product_of_slopes(&SLOPES).unwrap()
     }
 
-    fn calculate_for_idx(&self, skip: usize) -> usize {
-        self.0
-            .iter()
-            .enumerate()
-            .skip(1)
-            .map(|(idx, v)| if v[(idx * skip) % v.len()] { 1 } else { 0 })
-            .sum()
+    /// Counts the trees hit going `right` and `down` every step from the top left corner, until
+    /// passing the bottom row. The map repeats to the right. Returns `None` if `down` is 0, since
+    /// the bottom can never be reached.
+    pub fn trees_on_slope(&self, right: usize, down: usize) -> Option<usize> {
+        if down == 0 {
+            return None;
+        }
+        if self.rows.is_empty() {
+            return Some(0);
+        }
+
+        // keep a running column instead of taking a modulo for every row
+        let right = right % self.width;
+        let mut column = 0;
+        let mut count = 0;
+        let mut row = down;
+        while row < self.rows.len() {
+            column += right;
+            if column >= self.width {
+                column -= self.width;
+            }
+            count += (self.rows[row] >> column & 1) as usize;
+            row += down;
+        }
+        Some(count)
     }
 
-    fn calculate_for_double_idx(&self, skip: usize) -> usize {
-        self.0
+    pub fn product_of_slopes(&self, slopes: &[(usize, usize)]) -> Option<usize> {
+        slopes
             .iter()
-            .step_by(2)
-            .enumerate()
-            .skip(1)
-            .map(|(idx, v)| if v[(idx * skip) % v.len()] { 1 } else { 0 })
-            .sum()
+            .map(|&(right, down)| self.trees_on_slope(right, down))
+            .product()
+    }
+
+    /// Tries every slope within the bounds, returning the best tree count and all slopes that
+    /// reach it, ordered by `right` and then `down`. Slopes going down 0 are skipped.
+    pub fn search(
+        &self,
+        rights: RangeInclusive<usize>,
+        downs: RangeInclusive<usize>,
+        goal: Goal,
+    ) -> Option<(usize, Vec<(usize, usize)>)> {
+        let mut best: Option<(usize, Vec<(usize, usize)>)> = None;
+        for right in rights {
+            for down in downs.clone() {
+                let trees = match self.trees_on_slope(right, down) {
+                    Some(trees) => trees,
+                    None => continue,
+                };
+
+                match &mut best {
+                    Some((best_trees, slopes)) if *best_trees == trees => {
+                        slopes.push((right, down))
+                    }
+                    Some((best_trees, _)) if !goal.is_better(trees, *best_trees) => (),
+                    _ => best = Some((trees, vec![(right, down)])),
+                }
+            }
+        }
+        best
+    }
+}
+
+/// The slopes for part 2, as `(right, down)`.
+const SLOPES: [(usize, usize); 5] = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+enum Goal {
+    Fewest,
+    Most,
+}
+
+impl Goal {
+    fn is_better(self, trees: usize, than: usize) -> bool {
+        match self {
+            Goal::Fewest => trees < than,
+            Goal::Most => trees > than,
+        }
     }
 }
 
+const USAGE: &str =
+    "usage: day3 [slopes <right>,<down>... | search <right>-<right> <down>-<down> fewest|most | render <right>,<down> [--color] | bench [iterations]]";
+
 fn main() {
-    let (_, time_total) = time(||
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    match args.as_slice() {
+        [] => run_challenge(),
+        ["slopes", slopes @ ..] if !slopes.is_empty() => {
+            let slopes = slopes
+                .iter()
+                .map(|s| parse_pair(s, ','))
+                .collect::<Option<Vec<_>>>()
+                .unwrap_or_else(|| usage());
+            run_slopes(&slopes);
+        }
+        ["search", rights, downs, goal] => {
+            let (rights, downs) = match (parse_pair(rights, '-'), parse_pair(downs, '-')) {
+                (Some(rights), Some(downs)) => (rights, downs),
+                _ => usage(),
+            };
+            let goal = match *goal {
+                "fewest" => Goal::Fewest,
+                "most" => Goal::Most,
+                _ => usage(),
+            };
+            run_search(rights.0..=rights.1, downs.0..=downs.1, goal);
+        }
+        ["bench"] => run_bench(10_000),
+        ["bench", iterations] => run_bench(iterations.parse().unwrap_or_else(|_| usage())),
+        ["render", slope, rest @ ..] => {
+            let (right, down) = parse_pair(slope, ',').unwrap_or_else(|| usage());
+            let style = match rest {
+                [] => Style::Plain,
+                ["--color"] => Style::Ansi,
+                _ => usage(),
+            };
+            match read_map().render(right, down, style) {
+                Some(rendered) => print!("{}", rendered),
+                None => {
+                    eprintln!("a slope going down 0 never reaches the bottom");
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => usage(),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    std::process::exit(1);
+}
+
+fn parse_pair(s: &str, separator: char) -> Option<(usize, usize)> {
+    let mut split = s.split(separator);
+    let pair = (split.next()?.parse().ok()?, split.next()?.parse().ok()?);
+    match split.next() {
+        Some(_) => None,
+        None => Some(pair),
+    }
+}
+
+fn run_slopes(slopes: &[(usize, usize)]) {
+    let map = read_map();
+    for &(right, down) in slopes {
+        match map.trees_on_slope(right, down) {
+            Some(trees) => println!("right {}, down {}: {} trees", right, down, trees),
+            None => println!("right {}, down {}: never reaches the bottom", right, down),
+        }
+    }
+    if let Some(product) = map.product_of_slopes(slopes) {
+        println!("product: {}", product);
+    }
+}
+
+fn run_search(rights: RangeInclusive<usize>, downs: RangeInclusive<usize>, goal: Goal) {
+    let map = read_map();
+    match map.search(rights, downs, goal) {
+        Some((trees, slopes)) => {
+            println!("{} trees", trees);
+            for (right, down) in slopes {
+                println!("right {}, down {}", right, down);
+            }
+        }
+        None => println!("no slopes within these bounds"),
+    }
+}
+
+/// Solves both parts many times, since a single run is too quick to time reliably.
+fn run_bench(iterations: u32) {
+    let input = read_map();
+    let (_, time_solving_1) = time(|| {
+        for _ in 0..iterations {
+            std::hint::black_box(std::hint::black_box(&input).solve_1());
+        }
+    });
+    let (_, time_solving_2) = time(|| {
+        for _ in 0..iterations {
+            std::hint::black_box(std::hint::black_box(&input).solve_2());
+        }
+    });
+
+    println!("took {:?} to solve 1", time_solving_1 / iterations);
+    println!("took {:?} to solve 2", time_solving_2 / iterations);
+}
 
-    {
-        let (stdin, time_reading) = time(|| read_stdin());
+fn run_challenge() {
+    let (_, time_total) = time(|| {
+        let (stdin, time_reading) = time(read_stdin);
         let (input, time_parsing) = time(|| parse_string(&stdin));
+        let input = input.unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
 
         let (count_correct_1, time_solving_1) = time(|| input.solve_1());
         let (count_correct_2, time_solving_2) = time(|| input.solve_2());
-        
+
         println!("solution 1: {}", count_correct_1);
         println!("solution 2: {}", count_correct_2);
-        
+
         println!("took {:?} to read stdin", time_reading);
         println!("took {:?} to parse input", time_parsing);
         println!("took {:?} to solve 1", time_solving_1);
@@ -71,30 +252,118 @@ fn main() {
     println!("took {:?} in total", time_total);
 }
 
-fn parse_string(input: &str) -> Map {
-    let mut total_vec = Vec::new();
-    for line in input.split('\n') {
-        if line.len() == 0 {
+/// Something in the input that isn't a valid map, with its 1-based line.
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum ParseError {
+    TooWide {
+        line: usize,
+        width: usize,
+    },
+    UnevenWidth {
+        line: usize,
+        width: usize,
+        expected: usize,
+    },
+    InvalidChar {
+        line: usize,
+        column: usize,
+        found: char,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::TooWide { line, width } => write!(
+                f,
+                "line {}: rows can be at most 64 wide, found {}",
+                line, width
+            ),
+            ParseError::UnevenWidth {
+                line,
+                width,
+                expected,
+            } => write!(
+                f,
+                "line {}: row is {} wide, but the first row is {}",
+                line, width, expected
+            ),
+            ParseError::InvalidChar {
+                line,
+                column,
+                found,
+            } => write!(
+                f,
+                "line {}, column {}: {:?} is neither '#' nor '.'",
+                line, column, found
+            ),
+        }
+    }
+}
+
+/// Reads the map on stdin, exiting if it isn't valid.
+fn read_map() -> Map {
+    parse_string(&read_stdin()).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Reads rows up to the first empty line. Every row must be as wide as the first, and at most 64
+/// wide.
+fn parse_string(input: &str) -> Result<Map, ParseError> {
+    let mut rows = Vec::new();
+    let mut width = None;
+    for (idx, line) in input.split('\n').enumerate() {
+        if line.is_empty() {
             break;
         }
-        let vec: Vec<bool> = line
-            .bytes()
-            .map(|b| match b as char {
-                '#' => true,
-                '.' => false,
-                _ => panic!("invalid input char: {}", b as char),
-            })
-            .collect();
-        total_vec.push(vec);
+
+        let mut row = 0u64;
+        let mut len = 0;
+        for (column, c) in line.chars().enumerate() {
+            match c {
+                '#' if column < 64 => row |= 1 << column,
+                '.' | '#' => (),
+                found => {
+                    return Err(ParseError::InvalidChar {
+                        line: idx + 1,
+                        column: column + 1,
+                        found,
+                    })
+                }
+            }
+            len += 1;
+        }
+        if len > 64 {
+            return Err(ParseError::TooWide {
+                line: idx + 1,
+                width: len,
+            });
+        }
+        match width {
+            Some(expected) if expected != len => {
+                return Err(ParseError::UnevenWidth {
+                    line: idx + 1,
+                    width: len,
+                    expected,
+                })
+            }
+            _ => width = Some(len),
+        }
+        rows.push(row);
     }
-    Map(total_vec)
+    Ok(Map {
+        rows,
+        width: width.unwrap_or(0),
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::parse_string;
+    use crate::*;
 
-    const TEST_INPUT: &str = "\
+    pub(crate) const TEST_INPUT: &str = "\
         ..##.......\n\
         #...#...#..\n\
         .#....#..#.\n\
@@ -109,19 +378,81 @@ mod tests {
 
     #[test]
     fn test_parse() {
-        let parsed = parse_string(TEST_INPUT);
+        let parsed = parse_string(TEST_INPUT).unwrap();
         assert_eq!(parsed.to_string(), TEST_INPUT);
     }
 
     #[test]
     fn test_1() {
-        let parsed = parse_string(TEST_INPUT);
+        let parsed = parse_string(TEST_INPUT).unwrap();
         assert_eq!(parsed.solve_1(), 7);
     }
 
     #[test]
     fn test_2() {
-        let parsed = parse_string(TEST_INPUT);
+        let parsed = parse_string(TEST_INPUT).unwrap();
         assert_eq!(parsed.solve_2(), 336);
     }
+
+    #[test]
+    fn test_slopes() {
+        let parsed = parse_string(TEST_INPUT).unwrap();
+        assert_eq!(Some(7), parsed.trees_on_slope(3, 1));
+        assert_eq!(Some(2), parsed.trees_on_slope(1, 2));
+        assert_eq!(Some(3), parsed.trees_on_slope(0, 1));
+        assert_eq!(Some(1), parsed.trees_on_slope(2, 3));
+        assert_eq!(Some(0), parsed.trees_on_slope(1, 11));
+        assert_eq!(
+            parsed.trees_on_slope(3, 1),
+            parsed.trees_on_slope(3 + 11 * 1000, 1)
+        );
+        assert_eq!(None, parsed.trees_on_slope(1, 0));
+    }
+
+    #[test]
+    fn test_search() {
+        let parsed = parse_string(TEST_INPUT).unwrap();
+        let (most, slopes) = parsed.search(0..=11, 1..=1, Goal::Most).unwrap();
+        assert_eq!(7, most);
+        assert!(slopes.contains(&(3, 1)));
+
+        let (fewest, slopes) = parsed.search(0..=3, 0..=3, Goal::Fewest).unwrap();
+        assert_eq!(0, fewest);
+        assert!(slopes.iter().all(|&(_, down)| down > 0));
+
+        assert_eq!(None, parsed.search(0..=3, 0..=0, Goal::Most));
+    }
+
+    #[test]
+    fn test_invalid_maps() {
+        let wide = "#".repeat(64);
+        let map = parse_string(&format!("{}\n{}\n", wide, wide)).unwrap();
+        assert_eq!(64, map.width);
+        assert_eq!(Some(1), map.trees_on_slope(63, 1));
+
+        assert_eq!(
+            Err(ParseError::TooWide { line: 2, width: 65 }),
+            parse_string(&format!("{}\n{}.", wide, wide)).map(|m| m.rows)
+        );
+        assert_eq!(
+            Err(ParseError::UnevenWidth {
+                line: 3,
+                width: 2,
+                expected: 3
+            }),
+            parse_string("#..\n..#\n.#\n").map(|m| m.rows)
+        );
+        assert_eq!(
+            Err(ParseError::InvalidChar {
+                line: 2,
+                column: 2,
+                found: 'x'
+            }),
+            parse_string("#..\n.x.").map(|m| m.rows)
+        );
+        assert_eq!(
+            "line 3: row is 2 wide, but the first row is 3",
+            parse_string("#..\n..#\n.#").unwrap_err().to_string()
+        );
+    }
 }
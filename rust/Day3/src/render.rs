@@ -0,0 +1,104 @@
+use crate::Map;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Style {
+    Plain,
+    /// Colors the route using ANSI escape codes, for printing to a terminal.
+    Ansi,
+}
+
+impl Style {
+    fn tree_hit(self) -> &'static str {
+        match self {
+            Style::Plain => "X",
+            Style::Ansi => "\x1b[1;31mX\x1b[0m",
+        }
+    }
+
+    fn open_passed(self) -> &'static str {
+        match self {
+            Style::Plain => "O",
+            Style::Ansi => "\x1b[1;32mO\x1b[0m",
+        }
+    }
+}
+
+impl Map {
+    /// Draws the map with the route for a slope on top of it, like the puzzle description does:
+    /// trees that are hit become `X` and open squares that are passed become `O`. The map is
+    /// repeated to the right for as long as the route needs. Returns `None` if `down` is 0.
+    pub fn render(&self, right: usize, down: usize, style: Style) -> Option<String> {
+        if down == 0 {
+            return None;
+        }
+        if self.rows.is_empty() {
+            return Some(String::new());
+        }
+        let width = self.width;
+
+        let last_column = (self.rows.len() - 1) / down * right;
+        let repeats = last_column / width + 1;
+
+        let mut string = String::new();
+        for idx in 0..self.rows.len() {
+            // the starting square isn't passed, the toboggan only starts there
+            let passed = match idx % down {
+                0 if idx > 0 => Some(idx / down * right),
+                _ => None,
+            };
+
+            for column in 0..width * repeats {
+                match (passed == Some(column), self.is_tree(idx, column)) {
+                    (true, true) => string += style.tree_hit(),
+                    (true, false) => string += style.open_passed(),
+                    (false, true) => string.push('#'),
+                    (false, false) => string.push('.'),
+                }
+            }
+            string.push('\n');
+        }
+
+        Some(string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_string, tests::TEST_INPUT};
+
+    #[test]
+    fn test_render() {
+        let map = parse_string(TEST_INPUT).unwrap();
+        let rendered = map.render(3, 1, Style::Plain).unwrap();
+
+        // the same as the puzzle description, up to where the route ends
+        let expected = "\
+            ..##.........##.........##.......\n\
+            #..O#...#..#...#...#..#...#...#..\n\
+            .#....X..#..#....#..#..#....#..#.\n\
+            ..#.#...#O#..#.#...#.#..#.#...#.#\n\
+            .#...##..#..X...##..#..#...##..#.\n\
+            ..#.##.......#.X#.......#.##.....\n\
+            .#.#.#....#.#.#.#.O..#.#.#.#....#\n\
+            .#........#.#........X.#........#\n\
+            #.##...#...#.##...#...#.X#...#...\n\
+            #...##....##...##....##...#X....#\n\
+            .#..#...#.#.#..#...#.#.#..#...X.#\n";
+        assert_eq!(expected, rendered);
+        assert_eq!(7, rendered.matches('X').count());
+    }
+
+    #[test]
+    fn test_render_steep() {
+        let map = parse_string("..#\n#..\n.#.\n...\n#..").unwrap();
+        assert_eq!(
+            Some("..#\n#..\n.X.\n...\n#.O\n".to_owned()),
+            map.render(1, 2, Style::Plain)
+        );
+        assert_eq!(None, map.render(1, 0, Style::Plain));
+
+        let colored = map.render(1, 2, Style::Ansi).unwrap();
+        assert!(colored.starts_with("..#\n#..\n.\x1b[1;31mX\x1b[0m.\n"));
+    }
+}
@@ -0,0 +1,272 @@
+use std::fmt;
+
+/// The questions that can be answered, each with its own character. Holds at most 128.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Alphabet {
+    chars: Vec<char>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum AlphabetError {
+    TooLong(usize),
+    Repeated(char),
+}
+
+impl fmt::Display for AlphabetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlphabetError::TooLong(len) => {
+                write!(f, "alphabet has {} characters, at most 128 fit", len)
+            }
+            AlphabetError::Repeated(c) => write!(f, "alphabet has '{}' more than once", c),
+        }
+    }
+}
+
+impl Default for Alphabet {
+    /// The questions `a` to `z`.
+    fn default() -> Self {
+        Self {
+            chars: ('a'..='z').collect(),
+        }
+    }
+}
+
+impl Alphabet {
+    pub fn new(chars: &str) -> Result<Self, AlphabetError> {
+        let chars = chars.chars().collect::<Vec<_>>();
+        if chars.len() > AnswerSet::CAPACITY {
+            return Err(AlphabetError::TooLong(chars.len()));
+        }
+        for (idx, c) in chars.iter().enumerate() {
+            if chars[..idx].contains(c) {
+                return Err(AlphabetError::Repeated(*c));
+            }
+        }
+        Ok(Self { chars })
+    }
+
+    pub fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    pub fn index(&self, c: char) -> Option<usize> {
+        self.chars.iter().position(|&a| a == c)
+    }
+
+    pub fn char(&self, index: usize) -> char {
+        self.chars[index]
+    }
+}
+
+/// The questions someone answered yes to, as indices into an [`Alphabet`].
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Default)]
+pub struct AnswerSet(u128);
+
+impl AnswerSet {
+    pub const CAPACITY: usize = 128;
+
+    pub fn from_indices(indices: impl IntoIterator<Item = usize>) -> Self {
+        let mut set = Self::default();
+        for index in indices {
+            set.insert(index);
+        }
+        set
+    }
+
+    pub fn insert(&mut self, index: usize) {
+        self.0 |= 1 << index;
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.0 >> index & 1 == 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = usize> {
+        (0..Self::CAPACITY).filter(move |&i| self.contains(i))
+    }
+}
+
+/// The answers of everyone in a group, one set per member.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Group {
+    /// The 1-based line of the first member.
+    pub line: usize,
+    pub members: Vec<AnswerSet>,
+}
+
+impl Group {
+    /// The questions anyone answered.
+    pub fn anyone(&self) -> AnswerSet {
+        self.members
+            .iter()
+            .fold(AnswerSet::default(), |a, &b| a.union(b))
+    }
+
+    /// The questions everyone answered, which is nothing for a group without members.
+    pub fn everyone(&self) -> AnswerSet {
+        let mut members = self.members.iter().copied();
+        match members.next() {
+            Some(first) => members.fold(first, AnswerSet::intersection),
+            None => AnswerSet::default(),
+        }
+    }
+
+    /// The questions at least `k` members answered. `k` of 0 counts as 1.
+    pub fn at_least(&self, k: usize) -> AnswerSet {
+        let mut counts = [0; AnswerSet::CAPACITY];
+        for member in &self.members {
+            for index in member.iter() {
+                counts[index] += 1;
+            }
+        }
+        AnswerSet::from_indices((0..AnswerSet::CAPACITY).filter(|&i| counts[i] >= k.max(1)))
+    }
+}
+
+/// A character that isn't in the alphabet.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct InputError {
+    /// 1-based.
+    pub line: usize,
+    /// 1-based, counting characters.
+    pub column: usize,
+    pub found: char,
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {:?} is not a question",
+            self.line, self.column, self.found
+        )
+    }
+}
+
+/// Reads groups separated by blank lines, with one member per line. Whitespace around a member
+/// is ignored.
+pub fn parse_groups(input: &str, alphabet: &Alphabet) -> Result<Vec<Group>, InputError> {
+    let mut groups = vec![];
+    let mut current: Option<Group> = None;
+    for (idx, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            groups.extend(current.take());
+            continue;
+        }
+
+        let mut answers = AnswerSet::default();
+        let trimmed = line.trim_start();
+        let offset = line.chars().count() - trimmed.chars().count();
+        for (column, c) in trimmed.trim_end().chars().enumerate() {
+            match alphabet.index(c) {
+                Some(index) => answers.insert(index),
+                None => {
+                    return Err(InputError {
+                        line: idx + 1,
+                        column: offset + column + 1,
+                        found: c,
+                    })
+                }
+            }
+        }
+
+        let group = current.get_or_insert_with(|| Group {
+            line: idx + 1,
+            members: vec![],
+        });
+        group.members.push(answers);
+    }
+    groups.extend(current);
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb";
+
+    #[test]
+    fn test_example() {
+        let groups = parse_groups(INPUT, &Alphabet::default()).unwrap();
+        let anyone = groups.iter().map(|g| g.anyone().len()).collect::<Vec<_>>();
+        let everyone = groups
+            .iter()
+            .map(|g| g.everyone().len())
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![3, 3, 3, 1, 1], anyone);
+        assert_eq!(vec![3, 0, 1, 1, 1], everyone);
+        assert_eq!(
+            vec![1, 3, 7, 10, 15],
+            groups.iter().map(|g| g.line).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_at_least() {
+        let groups = parse_groups("ab\nac\nad\nbx\n", &Alphabet::default()).unwrap();
+        let group = &groups[0];
+        let alphabet = Alphabet::default();
+        let to_string = |set: AnswerSet| set.iter().map(|i| alphabet.char(i)).collect::<String>();
+
+        assert_eq!("abcdx", to_string(group.at_least(1)));
+        assert_eq!("ab", to_string(group.at_least(2)));
+        assert_eq!("a", to_string(group.at_least(3)));
+        assert_eq!("", to_string(group.at_least(4)));
+        assert_eq!(group.anyone(), group.at_least(0));
+        assert_eq!(group.everyone(), group.at_least(group.members.len()));
+        assert_eq!(AnswerSet::default(), Group::default().everyone());
+    }
+
+    #[test]
+    fn test_alphabets() {
+        let alphabet = Alphabet::new("AZ09é").unwrap();
+        let groups = parse_groups("  A9\r\n0é \r\n\r\nZ", &alphabet).unwrap();
+        assert_eq!(2, groups.len());
+        assert_eq!(
+            AnswerSet::from_indices(vec![0, 2, 3, 4]),
+            groups[0].anyone()
+        );
+        assert_eq!(AnswerSet::default(), groups[0].everyone());
+
+        let all = (0..128u8).map(char::from).collect::<String>();
+        let alphabet = Alphabet::new(&all).unwrap();
+        let set = AnswerSet::from_indices(alphabet.index('\x7f'));
+        assert_eq!(1, set.len());
+        assert!(set.contains(127));
+        assert_eq!(
+            Err(AlphabetError::TooLong(129)),
+            Alphabet::new(&(all + "é"))
+        );
+        assert_eq!(Err(AlphabetError::Repeated('a')), Alphabet::new("aba"));
+
+        assert_eq!(
+            Err(InputError {
+                line: 2,
+                column: 3,
+                found: 'A',
+            }),
+            parse_groups("ab\n bA", &Alphabet::default())
+        );
+        assert_eq!(
+            "line 2, column 3: 'A' is not a question",
+            parse_groups("ab\n bA", &Alphabet::default())
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
@@ -1,38 +1,70 @@
 include!("../../helpers.rs");
-struct GroupData(u32, u32);
 
-impl Default for GroupData {
-    fn default() -> Self {
-        GroupData(0, (1 << 27) - 1)
-    }
-}
+mod answers;
+#[path = "../../quote.rs"]
+mod quote;
+mod stats;
 
-impl GroupData {
-    pub fn register_answers(&mut self, chars: impl Iterator<Item = u8>) {
-        let mut answers = 0;
-        for c in chars {
-            let bit = c - b'a';
-            answers |= 1 << bit;
-        }
+use answers::{Alphabet, Group};
+use stats::Stats;
 
-        self.0 |= answers;
-        self.1 &= answers;
-    }
+const USAGE: &str = "usage: day6 [at-least <k> | stats [--json]] [--alphabet <questions>]";
 
-    pub fn get_answer_count_1(&self) -> u32 {
-        self.0.count_ones()
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    let (args, alphabet) = match args.as_slice() {
+        [rest @ .., "--alphabet", chars] => match Alphabet::new(chars) {
+            Ok(alphabet) => (rest, alphabet),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        },
+        args => (args, Alphabet::default()),
+    };
+
+    match args {
+        [] => run_challenge(&alphabet),
+        ["at-least", k] if k.parse::<usize>().is_ok() => {
+            let k = k.parse().unwrap();
+            let groups = read_groups(&read_stdin(), &alphabet);
+            let total = groups.iter().map(|g| g.at_least(k).len()).sum::<usize>();
+            println!("{}", total);
+        }
+        ["stats"] => {
+            let groups = read_groups(&read_stdin(), &alphabet);
+            print!("{}", Stats::new(&groups, &alphabet).to_table());
+        }
+        ["stats", "--json"] => {
+            let groups = read_groups(&read_stdin(), &alphabet);
+            print!("{}", Stats::new(&groups, &alphabet).to_json());
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
     }
+}
 
-    pub fn get_answer_count_2(&self) -> u32 {
-        self.1.count_ones()
+/// Parses the groups, exiting with an error if a character isn't in the alphabet.
+fn read_groups(input: &str, alphabet: &Alphabet) -> Vec<Group> {
+    match answers::parse_groups(input, alphabet) {
+        Ok(groups) => groups,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
     }
 }
 
-fn main() {
-    let (input_string, time_stdin) = time(|| read_stdin());
-    let (input, time_parsing) = time(|| parse_input(&input_string) as Vec<GroupData>);
-    let (solution_1, time_solve_1): (u32, std::time::Duration) = time(|| input.iter().map(|x| x.get_answer_count_1()).sum());
-    let (solution_2, time_solve_2): (u32, std::time::Duration) = time(|| input.iter().map(|x| x.get_answer_count_2()).sum());
+fn run_challenge(alphabet: &Alphabet) {
+    let (input_string, time_stdin) = time(read_stdin);
+    let (input, time_parsing) = time(|| read_groups(&input_string, alphabet));
+    let (solution_1, time_solve_1): (usize, std::time::Duration) =
+        time(|| input.iter().map(|x| x.anyone().len()).sum());
+    let (solution_2, time_solve_2): (usize, std::time::Duration) =
+        time(|| input.iter().map(|x| x.everyone().len()).sum());
 
     println!("solution 1: {:?}", solution_1);
     println!("solution 2: {:?}", solution_2);
@@ -41,16 +73,3 @@ fn main() {
     println!("took {:?} to solve part 1", time_solve_1);
     println!("took {:?} to solve part 2", time_solve_2);
 }
-
-fn parse_input(input: &str) -> Vec<GroupData> {
-    let mut vec = Vec::new();
-    for lines in input.split("\n\n") {
-        let mut data = GroupData::default();
-        for line in lines.split('\n').filter(|l| *l != "") {
-            data.register_answers(line.bytes());
-        }
-        vec.push(data);
-    }
-
-    vec
-}
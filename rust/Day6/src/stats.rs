@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    answers::{Alphabet, Group},
+    quote::quote,
+};
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct QuestionStats {
+    pub question: char,
+    /// The groups where anyone answered this question.
+    pub anyone: usize,
+    /// The groups where everyone answered this question.
+    pub everyone: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct GroupSummary {
+    /// 1-based.
+    pub group: usize,
+    pub line: usize,
+    pub size: usize,
+}
+
+/// The groups that share a number of answers between all their members.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Shared {
+    pub answers: usize,
+    pub groups: Vec<GroupSummary>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Stats {
+    /// Every question of the alphabet, in its order.
+    pub questions: Vec<QuestionStats>,
+    /// How many groups there are of each size.
+    pub sizes: BTreeMap<usize, usize>,
+    pub most_shared: Shared,
+    pub fewest_shared: Shared,
+}
+
+impl Stats {
+    pub fn new(groups: &[Group], alphabet: &Alphabet) -> Self {
+        let mut stats = Stats {
+            questions: (0..alphabet.len())
+                .map(|i| QuestionStats {
+                    question: alphabet.char(i),
+                    anyone: 0,
+                    everyone: 0,
+                })
+                .collect(),
+            ..Stats::default()
+        };
+
+        let shared = groups
+            .iter()
+            .map(|g| g.everyone().len())
+            .collect::<Vec<_>>();
+        let most = shared.iter().copied().max().unwrap_or(0);
+        let fewest = shared.iter().copied().min().unwrap_or(0);
+        stats.most_shared.answers = most;
+        stats.fewest_shared.answers = fewest;
+
+        for (idx, group) in groups.iter().enumerate() {
+            for index in group.anyone().iter() {
+                stats.questions[index].anyone += 1;
+            }
+            for index in group.everyone().iter() {
+                stats.questions[index].everyone += 1;
+            }
+            *stats.sizes.entry(group.members.len()).or_default() += 1;
+
+            let summary = GroupSummary {
+                group: idx + 1,
+                line: group.line,
+                size: group.members.len(),
+            };
+            if shared[idx] == most {
+                stats.most_shared.groups.push(summary);
+            }
+            if shared[idx] == fewest {
+                stats.fewest_shared.groups.push(summary);
+            }
+        }
+        stats
+    }
+
+    pub fn to_table(&self) -> String {
+        let mut string = "question  anyone  everyone\n".to_owned();
+        for question in &self.questions {
+            string += &format!(
+                "{:<8}  {:>6}  {:>8}\n",
+                question.question, question.anyone, question.everyone
+            );
+        }
+
+        string += "\nsize  groups\n";
+        for (size, count) in &self.sizes {
+            string += &format!("{:>4}  {:>6}\n", size, count);
+        }
+
+        if self.sizes.is_empty() {
+            return string;
+        }
+
+        string += "\n";
+        for (name, shared) in &[("most", &self.most_shared), ("fewest", &self.fewest_shared)] {
+            let groups = shared
+                .groups
+                .iter()
+                .map(|g| {
+                    let plural = if g.size == 1 { "" } else { "s" };
+                    format!(
+                        "group {} (line {}, {} member{})",
+                        g.group, g.line, g.size, plural
+                    )
+                })
+                .collect::<Vec<_>>();
+            string += &format!(
+                "{} shared answers: {} in {}\n",
+                name,
+                shared.answers,
+                groups.join(", ")
+            );
+        }
+        string
+    }
+
+    pub fn to_json(&self) -> String {
+        let questions = self
+            .questions
+            .iter()
+            .map(|q| {
+                format!(
+                    "{{\"question\":{},\"anyone\":{},\"everyone\":{}}}",
+                    quote(&q.question.to_string()),
+                    q.anyone,
+                    q.everyone
+                )
+            })
+            .collect::<Vec<_>>();
+        let sizes = self
+            .sizes
+            .iter()
+            .map(|(size, count)| format!("{{\"size\":{},\"groups\":{}}}", size, count))
+            .collect::<Vec<_>>();
+        let shared = |shared: &Shared| {
+            let groups = shared
+                .groups
+                .iter()
+                .map(|g| {
+                    format!(
+                        "{{\"group\":{},\"line\":{},\"size\":{}}}",
+                        g.group, g.line, g.size
+                    )
+                })
+                .collect::<Vec<_>>();
+            format!(
+                "{{\"answers\":{},\"groups\":[{}]}}",
+                shared.answers,
+                groups.join(",")
+            )
+        };
+
+        format!(
+            "{{\"questions\":[{}],\"group_sizes\":[{}],\"most_shared\":{},\"fewest_shared\":{}}}\n",
+            questions.join(","),
+            sizes.join(","),
+            shared(&self.most_shared),
+            shared(&self.fewest_shared)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answers::parse_groups;
+
+    const INPUT: &str = "abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb";
+
+    fn stats() -> Stats {
+        let alphabet = Alphabet::new("abc\"").unwrap();
+        Stats::new(&parse_groups(INPUT, &alphabet).unwrap(), &alphabet)
+    }
+
+    #[test]
+    fn test_table() {
+        assert_eq!(
+            "question  anyone  everyone\n\
+            a              4         3\n\
+            b              4         2\n\
+            c              3         1\n\
+            \"              0         0\n\
+            \n\
+            size  groups\n\
+            \x20  1       2\n\
+            \x20  2       1\n\
+            \x20  3       1\n\
+            \x20  4       1\n\
+            \n\
+            most shared answers: 3 in group 1 (line 1, 1 member)\n\
+            fewest shared answers: 0 in group 2 (line 3, 3 members)\n",
+            stats().to_table()
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let json = stats().to_json();
+        assert!(
+            json.starts_with("{\"questions\":[{\"question\":\"a\",\"anyone\":4,\"everyone\":3},")
+        );
+        assert!(json.contains("{\"question\":\"\\\"\",\"anyone\":0,\"everyone\":0}],"));
+        assert!(json.contains("\"group_sizes\":[{\"size\":1,\"groups\":2},"));
+        assert!(json.ends_with(
+            "\"most_shared\":{\"answers\":3,\"groups\":[{\"group\":1,\"line\":1,\"size\":1}]},\
+            \"fewest_shared\":{\"answers\":0,\"groups\":[{\"group\":2,\"line\":3,\"size\":3}]}}\n"
+        ));
+
+        let empty = Stats::new(&[], &Alphabet::default());
+        assert_eq!(Shared::default(), empty.most_shared);
+        assert!(empty.to_json().contains("\"group_sizes\":[],"));
+        assert!(empty.to_table().ends_with("size  groups\n"));
+    }
+}
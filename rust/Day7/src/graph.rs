@@ -0,0 +1,193 @@
+use crate::{
+    quote::quote,
+    string_interner::{StringInterner, StringKey},
+};
+
+/// An edge in the bag graph: `count` bags of `color`. In the reverse adjacency, `color` is the
+/// bag that contains the other one instead.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct ContainedLuggage {
+    pub color: StringKey,
+    pub count: u64,
+}
+
+/// Directed multigraph of bag colors, with an edge from each bag to every bag it must contain.
+#[derive(Default)]
+pub struct BagGraph {
+    colors: StringInterner,
+    forward: Vec<Vec<ContainedLuggage>>,
+    reverse: Vec<Vec<ContainedLuggage>>,
+    /// The (1-based) input lines on which each color's rule is given.
+    definitions: Vec<Vec<usize>>,
+    /// The (1-based) input lines that aren't rules, with what is wrong with each.
+    malformed: Vec<(usize, String)>,
+}
+
+impl BagGraph {
+    /// Gets the key for a color, adding it to the graph if it is new.
+    pub fn add_color(&mut self, color: &str) -> StringKey {
+        let key = self.colors.intern(color).expect("too many colors");
+        if key.as_usize() >= self.forward.len() {
+            self.forward.resize(key.as_usize() + 1, vec![]);
+            self.reverse.resize(key.as_usize() + 1, vec![]);
+            self.definitions.resize(key.as_usize() + 1, vec![]);
+        }
+        key
+    }
+
+    /// Records that the rule for `color` is given on `line`.
+    pub fn define(&mut self, color: StringKey, line: usize) {
+        self.definitions[color.as_usize()].push(line);
+    }
+
+    /// Records that `line` couldn't be read as a rule.
+    pub fn add_malformed(&mut self, line: usize, reason: String) {
+        self.malformed.push((line, reason));
+    }
+
+    pub fn add_edge(&mut self, container: StringKey, contained: StringKey, count: u64) {
+        self.forward[container.as_usize()].push(ContainedLuggage {
+            color: contained,
+            count,
+        });
+        self.reverse[contained.as_usize()].push(ContainedLuggage {
+            color: container,
+            count,
+        });
+    }
+
+    pub fn try_get_key(&self, color: &str) -> Option<StringKey> {
+        self.colors.try_get_key(color)
+    }
+
+    pub fn name(&self, color: StringKey) -> &str {
+        self.colors.lookup(color)
+    }
+
+    pub fn colors(&self) -> impl Iterator<Item = StringKey> {
+        self.colors.keys()
+    }
+
+    /// The bags that a bag of this color must directly contain.
+    pub fn contents(&self, color: StringKey) -> &[ContainedLuggage] {
+        &self.forward[color.as_usize()]
+    }
+
+    /// The bags that directly contain a bag of this color.
+    pub fn containers(&self, color: StringKey) -> &[ContainedLuggage] {
+        &self.reverse[color.as_usize()]
+    }
+
+    /// The lines on which the rule for this color is given. Empty if the color is only ever
+    /// mentioned inside other bags.
+    pub fn definitions(&self, color: StringKey) -> &[usize] {
+        &self.definitions[color.as_usize()]
+    }
+
+    /// The lines that couldn't be read as rules, in input order.
+    pub fn malformed(&self) -> &[(usize, String)] {
+        &self.malformed
+    }
+
+    pub fn color_count(&self) -> usize {
+        self.forward.len()
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut string = String::from("digraph bags {\n");
+        for color in self.colors() {
+            string += &format!("    {};\n", quote(self.name(color)));
+        }
+        for color in self.colors() {
+            for edge in self.contents(color) {
+                string += &format!(
+                    "    {} -> {} [label={}];\n",
+                    quote(self.name(color)),
+                    quote(self.name(edge.color)),
+                    edge.count
+                );
+            }
+        }
+        string += "}\n";
+        string
+    }
+
+    pub fn to_json(&self) -> String {
+        let colors = self
+            .colors()
+            .map(|c| quote(self.name(c)))
+            .collect::<Vec<_>>();
+        let edges = self
+            .colors()
+            .flat_map(|c| self.contents(c).iter().map(move |e| (c, e)))
+            .map(|(c, e)| {
+                format!(
+                    "{{\"from\":{},\"to\":{},\"count\":{}}}",
+                    quote(self.name(c)),
+                    quote(self.name(e.color)),
+                    e.count
+                )
+            })
+            .collect::<Vec<_>>();
+
+        format!(
+            "{{\"colors\":[{}],\"edges\":[{}]}}\n",
+            colors.join(","),
+            edges.join(",")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_graph() -> BagGraph {
+        let mut graph = BagGraph::default();
+        let red = graph.add_color("light red");
+        let white = graph.add_color("bright white");
+        let gold = graph.add_color("shiny gold");
+        graph.add_edge(red, white, 1);
+        graph.add_edge(red, gold, 2);
+        graph.add_edge(white, gold, 1);
+        graph
+    }
+
+    #[test]
+    fn test_adjacency() {
+        let graph = small_graph();
+        let red = graph.try_get_key("light red").unwrap();
+        let gold = graph.try_get_key("shiny gold").unwrap();
+
+        assert_eq!(3, graph.color_count());
+        assert_eq!(2, graph.contents(red).len());
+        assert_eq!(0, graph.containers(red).len());
+        assert_eq!(
+            vec!["light red", "bright white"],
+            graph
+                .containers(gold)
+                .iter()
+                .map(|e| graph.name(e.color))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_export() {
+        let graph = small_graph();
+
+        assert_eq!(
+            "digraph bags {\n    \"light red\";\n    \"bright white\";\n    \"shiny gold\";\n    \
+            \"light red\" -> \"bright white\" [label=1];\n    \"light red\" -> \"shiny gold\" [label=2];\n    \
+            \"bright white\" -> \"shiny gold\" [label=1];\n}\n",
+            graph.to_dot()
+        );
+        assert_eq!(
+            "{\"colors\":[\"light red\",\"bright white\",\"shiny gold\"],\"edges\":[\
+            {\"from\":\"light red\",\"to\":\"bright white\",\"count\":1},\
+            {\"from\":\"light red\",\"to\":\"shiny gold\",\"count\":2},\
+            {\"from\":\"bright white\",\"to\":\"shiny gold\",\"count\":1}]}\n",
+            graph.to_json()
+        );
+    }
+}
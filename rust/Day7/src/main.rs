@@ -1,44 +1,102 @@
+mod graph;
+mod query;
+#[path = "../../quote.rs"]
+mod quote;
+#[path = "../../scc.rs"]
+mod scc;
+#[path = "../../string_interner.rs"]
 mod string_interner;
+mod validation;
 
-use string_interner::{StringInterner, StringKey};
-use arr_macro::arr;
+use graph::BagGraph;
+use string_interner::StringKey;
 
 include!("../../helpers.rs");
 
-// TODO: store in tree/graph form
+const TARGET_COLOR: &str = "shiny gold";
 
-const LOOKUP_SIZE: usize = 1024;
-pub struct InputData {
-    map: [Vec<ContainedLuggage>; LOOKUP_SIZE],
-    cache: StringInterner,
-}
-
-impl Default for InputData {
-    fn default() -> Self {
-        Self {
-            cache: StringInterner::default(),
-            map: arr![vec![]; 1024], // manually entered
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    match args.as_slice() {
+        [] => run_challenge(),
+        ["dot"] => print!("{}", read_rules().to_dot()),
+        ["json"] => print!("{}", read_rules().to_json()),
+        ["validate"] => {
+            let problems = validation::validate(&parse_input(&read_stdin()));
+            for problem in &problems {
+                println!("{}", problem);
+            }
+            if !problems.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        ["containers", color] => run_query(|graph| {
+            let containers = query::eventual_containers(graph, color)?;
+            Ok(containers
+                .iter()
+                .map(|&c| format!("{}\n", graph.name(c)))
+                .collect())
+        }),
+        ["inside", color] => {
+            run_query(|graph| Ok(format!("{}\n", query::bags_inside(graph, color)?)))
+        }
+        ["tree", color] => run_query(|graph| query::containment_tree(graph, color)),
+        ["chain", from, to] => run_query(|graph| {
+            let chain = query::shortest_chain(graph, from, to)?;
+            let names = chain.iter().map(|&c| graph.name(c)).collect::<Vec<_>>();
+            Ok(format!("{}\n", names.join(" -> ")))
+        }),
+        _ => {
+            eprintln!(
+                "usage: day7 [dot | json | validate | containers <color> | inside <color> | tree <color> | chain <from> <to>]"
+            );
+            std::process::exit(1);
         }
     }
 }
 
-impl InputData {
-    pub fn look_up_container(&self, data: StringKey) -> &Vec<ContainedLuggage> {
-        &self.map[data.as_usize()]
+/// Parses the rules on stdin, exiting with the malformed lines if there are any.
+fn read_rules() -> BagGraph {
+    let graph = parse_input(&read_stdin());
+    let problems = validation::malformed(&graph);
+    if !problems.is_empty() {
+        for problem in &problems {
+            eprintln!("{}", problem);
+        }
+        std::process::exit(1);
     }
+    graph
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-pub struct ContainedLuggage {
-    color: StringKey,
-    count: u16,
+fn run_query<F>(query: F)
+where
+    F: FnOnce(&BagGraph) -> Result<String, query::QueryError>,
+{
+    let graph = read_rules();
+    match query(&graph) {
+        Ok(output) => print!("{}", output),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
 }
 
-fn main() {
-    let (stdin, time_reading) = time(|| read_stdin());
+fn run_challenge() {
+    let (stdin, time_reading) = time(read_stdin);
     let (input, time_parsing) = time(|| parse_input(&stdin));
-    let (solution_1, time_solving_1) = time(|| solve_1(&input));
-    let (solution_2, time_solving_2) = time(|| solve_2(&input));
+
+    let problems = validation::validate(&input);
+    if !problems.is_empty() {
+        for problem in &problems {
+            eprintln!("{}", problem);
+        }
+        std::process::exit(1);
+    }
+
+    let (solution_1, time_solving_1) = time(|| solve_1(&input, TARGET_COLOR).unwrap());
+    let (solution_2, time_solving_2) = time(|| solve_2(&input, TARGET_COLOR).unwrap());
 
     println!("solution 1: {:?}", solution_1);
     println!("solution 2: {:?}", solution_2);
@@ -48,125 +106,116 @@ fn main() {
     println!("took {:?} to solve 2", time_solving_2);
 }
 
-#[derive(Copy, Clone)]
-struct LookupEntry(u8);
-
-const TRUE: u8 = 1;
-const FALSE: u8 = 0;
-const UNKNOWN: u8 = 2;
-impl LookupEntry {
-    pub fn is_known(&self) -> bool {
-        self.0 != UNKNOWN
-    }
-    pub fn is_true(&self) -> bool {
-        self.0 == TRUE
-    }
-
-    pub fn new_unknown() -> Self {
-        LookupEntry(UNKNOWN)
-    }
-    pub fn from_bool(b: bool) -> Self {
-        LookupEntry(if b { TRUE } else { FALSE })
-    }
+fn solve_1(data: &BagGraph, color: &str) -> Result<usize, query::QueryError> {
+    query::eventual_containers(data, color).map(|c| c.len())
 }
 
-fn solve_1(data: &InputData) -> usize {
-    const TARGET_COLOR: &str = "shiny gold";
-    let target_color = data.cache.get_key(TARGET_COLOR);
-
-    let mut sum = 0;
-    let mut buffer = [LookupEntry::new_unknown(); LOOKUP_SIZE];
-    for k in (0..data.map.len()).map(|i| StringKey::from(i as u16)) {
-        // TODO: would create enumerator method
-        // checking against target_color again, since we don't want to return if we are target_color
-        let b = k != target_color && can_hold_color(k, target_color, data, &mut buffer);
-        sum += b as usize;
-    }
-
-    sum
+fn solve_2(data: &BagGraph, color: &str) -> Result<u64, query::QueryError> {
+    query::bags_inside(data, color)
 }
 
-fn solve_2(data: &InputData) -> u16 {
-    const TARGET_COLOR: &str = "shiny gold";
-    let target_color = data.cache.get_key(TARGET_COLOR);
-
-    check_required_bags(target_color, data)
-}
-
-fn can_hold_color(
+/// Returns `None` if the total does not fit in a `u64`. Expects the bags inside `color` not to
+/// contain each other.
+fn check_required_bags(
     color: StringKey,
-    target: StringKey,
-    data_set: &InputData,
-    cache: &mut [LookupEntry; LOOKUP_SIZE],
-) -> bool {
-    if color == target {
-        cache[color.as_usize()] = LookupEntry::from_bool(true);
-        return true;
+    data_set: &BagGraph,
+    cache: &mut [Option<u64>],
+) -> Option<u64> {
+    if let Some(total) = cache[color.as_usize()] {
+        return Some(total);
     }
 
-    let lookup = &cache[color.as_usize()];
-    if lookup.is_known() {
-        return lookup.is_true();
+    // each entry holds the next bag to count and the total of the ones before it
+    let mut stack = vec![(color, 0, 0u64)];
+    while let Some(top) = stack.last_mut() {
+        let (current, child, total) = *top;
+        match data_set.contents(current).get(child) {
+            Some(c) => match cache[c.color.as_usize()] {
+                Some(inside) => {
+                    top.1 += 1;
+                    top.2 = inside
+                        .checked_add(1)?
+                        .checked_mul(c.count)?
+                        .checked_add(total)?;
+                }
+                None => stack.push((c.color, 0, 0)),
+            },
+            None => {
+                cache[current.as_usize()] = Some(total);
+                stack.pop();
+            }
+        }
     }
 
-    let contained = data_set.look_up_container(color);
-    let found = contained
-        .iter()
-        .any(|c| can_hold_color(c.color, target, data_set, cache));
-
-    cache[color.as_usize()] = LookupEntry::from_bool(found);
-    found
+    cache[color.as_usize()]
 }
 
-fn check_required_bags(color: StringKey, data_set: &InputData) -> u16 {
-    let contained = data_set.look_up_container(color);
-    contained
-        .iter()
-        .map(|c| (check_required_bags(c.color, data_set) + 1) * c.count)
-        .sum()
-}
-
-fn parse_input(input: &str) -> InputData {
-    let mut data = InputData::default();
-
-    for line in input.split('\n') {
-        let mut split = line.split(" bags contain ");
-        let color = split.next().unwrap();
-        let things = split.next().unwrap();
+fn parse_input(input: &str) -> BagGraph {
+    let mut data = BagGraph::default();
 
-        let key = data.cache.intern(color);
-        // assert_eq!(data.map.len(), key.as_usize());
+    for (idx, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
 
-        data.map[key.as_usize()] = if things == "no other bags." {
-            vec![]
-        } else {
-            let mut v = vec![];
-            for s in things.trim_end_matches('.').split(", ") {
-                v.push(parse_contained(s, &mut data.cache));
+        match parse_rule(line) {
+            Ok((color, contents)) => {
+                let key = data.add_color(color);
+                data.define(key, idx + 1);
+                for (count, contained) in contents {
+                    let contained = data.add_color(contained);
+                    data.add_edge(key, contained, count);
+                }
             }
-            v
-        };
+            Err(reason) => data.add_malformed(idx + 1, reason),
+        }
     }
 
     data
 }
 
-fn parse_contained(s: &str, cache: &mut StringInterner) -> ContainedLuggage {
-    let count_unparsed = s.split(' ').next().unwrap();
-    let count_len = count_unparsed.len();
-    let count = count_unparsed.parse::<usize>().unwrap() as u16;
+/// A bag color and the `(count, color)` of every bag it must contain.
+type Rule<'a> = (&'a str, Vec<(u64, &'a str)>);
+
+fn parse_rule(line: &str) -> Result<Rule<'_>, String> {
+    let (color, things) = match line.split_once(" bags contain ") {
+        Some((color, things)) if !color.is_empty() => (color, things),
+        _ => return Err("expected \"<color> bags contain ...\"".to_owned()),
+    };
+    let things = things
+        .strip_suffix('.')
+        .ok_or_else(|| "expected the rule to end with '.'".to_owned())?;
 
-    let color_len = s.len() - (count_len + "bag".len() + 2 + if count == 1 { 0 } else { 1 });
-    let color = cache.intern(&s[count_len + 1..count_len + 1 + color_len]);
+    if things == "no other bags" {
+        return Ok((color, vec![]));
+    }
+    let contents = things
+        .split(", ")
+        .map(parse_contained)
+        .collect::<Result<_, _>>()?;
+    Ok((color, contents))
+}
 
-    ContainedLuggage { count, color }
+fn parse_contained(s: &str) -> Result<(u64, &str), String> {
+    let expected = || format!("expected \"<count> <color> bags\", found {:?}", s);
+    let (count, rest) = s.split_once(' ').ok_or_else(expected)?;
+    let count = count
+        .parse::<u64>()
+        .map_err(|_| format!("{:?} is not a number of bags", count))?;
+    let color = rest
+        .strip_suffix(" bags")
+        .or_else(|| rest.strip_suffix(" bag"))
+        .filter(|color| !color.is_empty())
+        .ok_or_else(expected)?;
+    Ok((count, color))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::*;
+    use crate::{graph::ContainedLuggage, *};
 
-    const TEST_DATA: &str = "\
+    pub(crate) const TEST_DATA: &str = "\
         light red bags contain 1 bright white bag, 2 muted yellow bags.\n\
         dark orange bags contain 3 bright white bags, 4 muted yellow bags.\n\
         bright white bags contain 1 shiny gold bag.\n\
@@ -193,38 +242,81 @@ mod tests {
         assert_eq!(
             vec![
                 ContainedLuggage {
-                    color: parsed.cache.get_key("bright white"),
+                    color: parsed.try_get_key("bright white").unwrap(),
                     count: 1,
                 },
                 ContainedLuggage {
-                    color: parsed.cache.get_key("muted yellow"),
+                    color: parsed.try_get_key("muted yellow").unwrap(),
                     count: 2,
                 },
             ],
-            parsed.map[parsed.cache.get_key("light red").as_usize()]
+            parsed.contents(parsed.try_get_key("light red").unwrap())
         );
 
         assert_eq!(
             Vec::<ContainedLuggage>::new(),
-            parsed.map[parsed.cache.get_key("dotted black").as_usize()],
+            parsed.contents(parsed.try_get_key("dotted black").unwrap()),
         )
     }
 
+    #[test]
+    fn test_blank_lines() {
+        let input = format!("\n{}\n\n", TEST_DATA.replacen('\n', "\r\n\n", 2));
+        let parsed = parse_input(&input);
+        assert!(parsed.malformed().is_empty());
+        assert_eq!(Ok(32), solve_2(&parsed, TARGET_COLOR));
+        assert_eq!(
+            &[2],
+            parsed.definitions(parsed.try_get_key("light red").unwrap())
+        );
+        assert_eq!(
+            &[6],
+            parsed.definitions(parsed.try_get_key("bright white").unwrap())
+        );
+    }
+
     #[test]
     fn test_solution_1() {
         let parsed = parse_input(TEST_DATA);
-        assert_eq!(4, solve_1(&parsed));
+        assert_eq!(Ok(4), solve_1(&parsed, TARGET_COLOR));
+        assert_eq!(Ok(7), solve_1(&parsed, "faded blue"));
+
+        // a cycle that can't hold the target doesn't matter
+        let cyclic = format!(
+            "{}\nclear red bags contain 1 clear blue bag.\n\
+            clear blue bags contain 1 clear red bag.",
+            TEST_DATA
+        );
+        assert_eq!(Ok(4), solve_1(&parse_input(&cyclic), TARGET_COLOR));
     }
 
     #[test]
     fn test_solution_2() {
         let parsed = parse_input(TEST_DATA);
-        assert_eq!(32, solve_2(&parsed));
+        assert_eq!(Ok(32), solve_2(&parsed, TARGET_COLOR));
+        assert_eq!(
+            Err(query::QueryError::UnknownColor("clear silver".to_owned())),
+            solve_2(&parsed, "clear silver")
+        );
+    }
+
+    #[test]
+    fn test_many_colors() {
+        // a single long chain of colors, each containing one bag of the next
+        let input = (0..300_000)
+            .map(|i| format!("color{} bags contain 1 color{} bag.\n", i, i + 1))
+            .collect::<String>()
+            + "color300000 bags contain no other bags.";
+        let parsed = parse_input(&input);
+        assert_eq!(300_001, parsed.color_count());
+        assert_eq!(Ok(300_000), solve_1(&parsed, "color300000"));
+        assert_eq!(Ok(300_000), solve_2(&parsed, "color0"));
+        assert_eq!(Ok(1), solve_2(&parsed, "color299999"));
     }
 
     #[test]
     fn test_solution_2_other() {
         let parsed = parse_input(TEST_DATA_2);
-        assert_eq!(126, solve_2(&parsed));
+        assert_eq!(Ok(126), solve_2(&parsed, TARGET_COLOR));
     }
 }
@@ -0,0 +1,263 @@
+use std::{collections::VecDeque, fmt};
+
+use crate::{check_required_bags, graph::BagGraph, string_interner::StringKey, validation};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum QueryError {
+    UnknownColor(String),
+    NoChain {
+        from: String,
+        to: String,
+    },
+    /// The answer would be infinite, since bags of this color eventually contain themselves.
+    Cycle(String),
+    /// The amount of bags inside a bag of this color does not fit in 64 bits.
+    Overflow(String),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::UnknownColor(color) => write!(f, "unknown bag color '{}'", color),
+            QueryError::NoChain { from, to } => {
+                write!(f, "{} bags can never contain {} bags", from, to)
+            }
+            QueryError::Cycle(color) => write!(f, "{} bags eventually contain themselves", color),
+            QueryError::Overflow(color) => {
+                write!(f, "{} bags contain too many bags to count", color)
+            }
+        }
+    }
+}
+
+pub fn key(graph: &BagGraph, color: &str) -> Result<StringKey, QueryError> {
+    graph
+        .try_get_key(color)
+        .ok_or_else(|| QueryError::UnknownColor(color.to_owned()))
+}
+
+/// All colors that can eventually contain a bag of `color`, not including `color` itself.
+pub fn eventual_containers(graph: &BagGraph, color: &str) -> Result<Vec<StringKey>, QueryError> {
+    let target = key(graph, color)?;
+
+    let mut seen = vec![false; graph.color_count()];
+    let mut stack = vec![target];
+    let mut containers = vec![];
+    while let Some(color) = stack.pop() {
+        for edge in graph.containers(color) {
+            if !seen[edge.color.as_usize()] {
+                seen[edge.color.as_usize()] = true;
+                if edge.color != target {
+                    containers.push(edge.color);
+                }
+                stack.push(edge.color);
+            }
+        }
+    }
+
+    Ok(containers)
+}
+
+/// The total amount of bags inside a bag of `color`.
+pub fn bags_inside(graph: &BagGraph, color: &str) -> Result<u64, QueryError> {
+    let root = key(graph, color)?;
+    if let Some(cyclic) = validation::find_cycle(graph, std::iter::once(root)) {
+        return Err(QueryError::Cycle(graph.name(cyclic).to_owned()));
+    }
+    check_required_bags(root, graph, &mut vec![None; graph.color_count()])
+        .ok_or_else(|| QueryError::Overflow(color.to_owned()))
+}
+
+/// Renders every bag inside a bag of `color` as an indented tree.
+pub fn containment_tree(graph: &BagGraph, color: &str) -> Result<String, QueryError> {
+    let root = key(graph, color)?;
+    let mut string = format!("{}\n", graph.name(root));
+
+    // each entry is a bag on the path from the root and the next of its contents to write
+    let mut path = vec![(root, 0)];
+    let mut on_path = vec![false; graph.color_count()];
+    on_path[root.as_usize()] = true;
+    while let Some(top) = path.last_mut() {
+        let (color, child) = *top;
+        top.1 += 1;
+        let edge = match graph.contents(color).get(child) {
+            Some(edge) => edge,
+            None => {
+                on_path[color.as_usize()] = false;
+                path.pop();
+                continue;
+            }
+        };
+
+        let indent = "  ".repeat(path.len());
+        string += &format!("{}{} {}", indent, edge.count, graph.name(edge.color));
+        if on_path[edge.color.as_usize()] {
+            string += " (cycle)\n";
+            continue;
+        }
+        string.push('\n');
+
+        on_path[edge.color.as_usize()] = true;
+        path.push((edge.color, 0));
+    }
+    Ok(string)
+}
+
+/// The shortest list of colors starting at `from` where each bag directly contains the next,
+/// ending at `to`.
+pub fn shortest_chain(
+    graph: &BagGraph,
+    from: &str,
+    to: &str,
+) -> Result<Vec<StringKey>, QueryError> {
+    let start = key(graph, from)?;
+    let end = key(graph, to)?;
+    if start == end {
+        return Ok(vec![start]);
+    }
+
+    // breadth-first, remembering where each color was first reached from
+    let mut previous = vec![None; graph.color_count()];
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+    while let Some(color) = queue.pop_front() {
+        for edge in graph.contents(color) {
+            if previous[edge.color.as_usize()].is_some() {
+                continue;
+            }
+            previous[edge.color.as_usize()] = Some(color);
+
+            if edge.color == end {
+                let mut chain = vec![end];
+                let mut current = color;
+                while current != start {
+                    chain.push(current);
+                    current = previous[current.as_usize()].unwrap();
+                }
+                chain.push(start);
+                chain.reverse();
+                return Ok(chain);
+            }
+            queue.push_back(edge.color);
+        }
+    }
+
+    Err(QueryError::NoChain {
+        from: from.to_owned(),
+        to: to.to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_input, tests::TEST_DATA};
+
+    fn names(graph: &BagGraph, keys: &[StringKey]) -> Vec<String> {
+        let mut names = keys
+            .iter()
+            .map(|&k| graph.name(k).to_owned())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_containers() {
+        let graph = parse_input(TEST_DATA);
+
+        let containers = eventual_containers(&graph, "shiny gold").unwrap();
+        assert_eq!(
+            vec!["bright white", "dark orange", "light red", "muted yellow"],
+            names(&graph, &containers)
+        );
+
+        let containers = eventual_containers(&graph, "dark olive").unwrap();
+        assert_eq!(5, containers.len());
+
+        assert_eq!(
+            Err(QueryError::UnknownColor("plaid magenta".to_owned())),
+            eventual_containers(&graph, "plaid magenta")
+        );
+    }
+
+    #[test]
+    fn test_inside() {
+        let graph = parse_input(TEST_DATA);
+        assert_eq!(Ok(32), bags_inside(&graph, "shiny gold"));
+        assert_eq!(Ok(7), bags_inside(&graph, "dark olive"));
+        assert_eq!(Ok(0), bags_inside(&graph, "faded blue"));
+
+        let graph = parse_input(
+            "bright white bags contain 1 shiny gold bag.\n\
+            shiny gold bags contain 2 bright white bags, 1 faded blue bag.\n\
+            faded blue bags contain no other bags.",
+        );
+        assert_eq!(
+            Err(QueryError::Cycle("bright white".to_owned())),
+            bags_inside(&graph, "bright white")
+        );
+        assert_eq!(Ok(0), bags_inside(&graph, "faded blue"));
+
+        let graph = parse_input(
+            "light red bags contain 4294967296 bright white bags.\n\
+            bright white bags contain 4294967296 faded blue bags.\n\
+            faded blue bags contain no other bags.",
+        );
+        assert_eq!(Ok(1 << 32), bags_inside(&graph, "bright white"));
+        assert_eq!(
+            Err(QueryError::Overflow("light red".to_owned())),
+            bags_inside(&graph, "light red")
+        );
+    }
+
+    #[test]
+    fn test_tree() {
+        let graph = parse_input(TEST_DATA);
+        assert_eq!(
+            Ok("dark olive\n  3 faded blue\n  4 dotted black\n".to_owned()),
+            containment_tree(&graph, "dark olive")
+        );
+
+        // the output grows with the square of the depth, so this stays shallower than the
+        // counting tests
+        let input = (0..5000)
+            .map(|i| format!("color{} bags contain 1 color{} bag.\n", i, i + 1))
+            .collect::<String>()
+            + "color5000 bags contain 1 color4000 bag.";
+        let tree = containment_tree(&parse_input(&input), "color0").unwrap();
+        let lines = tree.lines().collect::<Vec<_>>();
+        assert_eq!(5002, lines.len());
+        assert_eq!(format!("{}1 color5000", "  ".repeat(5000)), lines[5000]);
+        assert_eq!(
+            format!("{}1 color4000 (cycle)", "  ".repeat(5001)),
+            lines[5001]
+        );
+    }
+
+    #[test]
+    fn test_chain() {
+        let graph = parse_input(TEST_DATA);
+
+        let chain = shortest_chain(&graph, "light red", "dotted black").unwrap();
+        let chain = chain.iter().map(|&k| graph.name(k)).collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "light red",
+                "bright white",
+                "shiny gold",
+                "dark olive",
+                "dotted black"
+            ],
+            chain
+        );
+
+        assert_eq!(
+            Err(QueryError::NoChain {
+                from: "faded blue".to_owned(),
+                to: "light red".to_owned()
+            }),
+            shortest_chain(&graph, "faded blue", "light red")
+        );
+    }
+}
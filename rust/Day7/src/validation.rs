@@ -0,0 +1,288 @@
+use std::fmt;
+
+use crate::{graph::BagGraph, scc, string_interner::StringKey};
+
+/// Something wrong with the rules, with the lines of the rules that cause it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Problem {
+    /// A bag that must directly contain a bag of its own color.
+    SelfContainment {
+        color: String,
+        lines: Vec<usize>,
+    },
+    /// Bags that all eventually contain each other.
+    Cycle {
+        colors: Vec<String>,
+        lines: Vec<usize>,
+    },
+    /// A color that has no rule of its own. The lines are the rules that mention it.
+    Undefined {
+        color: String,
+        lines: Vec<usize>,
+    },
+    Duplicate {
+        color: String,
+        lines: Vec<usize>,
+    },
+    /// A line that isn't a rule at all.
+    Malformed {
+        reason: String,
+        lines: Vec<usize>,
+    },
+}
+
+impl Problem {
+    pub fn lines(&self) -> &[usize] {
+        match self {
+            Problem::SelfContainment { lines, .. }
+            | Problem::Cycle { lines, .. }
+            | Problem::Undefined { lines, .. }
+            | Problem::Duplicate { lines, .. }
+            | Problem::Malformed { lines, .. } => lines,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines = self
+            .lines()
+            .iter()
+            .map(|l| l.to_string())
+            .collect::<Vec<_>>();
+        let plural = if lines.len() == 1 { "" } else { "s" };
+        write!(f, "line{} {}: ", plural, lines.join(", "))?;
+
+        match self {
+            Problem::SelfContainment { color, .. } => {
+                write!(f, "{} bags contain themselves", color)
+            }
+            Problem::Cycle { colors, .. } => {
+                write!(f, "bags contain each other: {}", colors.join(", "))
+            }
+            Problem::Undefined { color, .. } => write!(f, "{} bags are never defined", color),
+            Problem::Duplicate { color, .. } => {
+                write!(f, "{} bags are defined more than once", color)
+            }
+            Problem::Malformed { reason, .. } => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// Checks the rules for problems that make the puzzle ambiguous or unsolvable, sorted by the
+/// first line involved.
+pub fn validate(graph: &BagGraph) -> Vec<Problem> {
+    let mut problems = malformed(graph);
+
+    for color in graph.colors() {
+        let name = graph.name(color).to_owned();
+        let definitions = graph.definitions(color);
+
+        if definitions.is_empty() {
+            let mut lines = graph
+                .containers(color)
+                .iter()
+                .flat_map(|e| graph.definitions(e.color).iter().copied())
+                .collect::<Vec<_>>();
+            lines.sort_unstable();
+            lines.dedup();
+            problems.push(Problem::Undefined { color: name, lines });
+            continue;
+        }
+
+        if definitions.len() > 1 {
+            problems.push(Problem::Duplicate {
+                color: name.clone(),
+                lines: definitions.to_vec(),
+            });
+        }
+        if graph.contents(color).iter().any(|e| e.color == color) {
+            problems.push(Problem::SelfContainment {
+                color: name,
+                lines: definitions.to_vec(),
+            });
+        }
+    }
+
+    for members in strongly_connected(graph) {
+        if members.len() < 2 {
+            continue;
+        }
+        let mut lines = members
+            .iter()
+            .flat_map(|&c| graph.definitions(c).iter().copied())
+            .collect::<Vec<_>>();
+        lines.sort_unstable();
+        problems.push(Problem::Cycle {
+            colors: members.iter().map(|&c| graph.name(c).to_owned()).collect(),
+            lines,
+        });
+    }
+
+    problems.sort_by_key(|p| p.lines().first().copied());
+    problems
+}
+
+/// The lines that couldn't be read as rules, in input order.
+pub fn malformed(graph: &BagGraph) -> Vec<Problem> {
+    graph
+        .malformed()
+        .iter()
+        .map(|(line, reason)| Problem::Malformed {
+            reason: reason.clone(),
+            lines: vec![*line],
+        })
+        .collect()
+}
+
+/// Finds a color that eventually contains itself, looking only at bags inside the `roots`
+/// (including the roots themselves).
+pub fn find_cycle(graph: &BagGraph, roots: impl Iterator<Item = StringKey>) -> Option<StringKey> {
+    const UNSEEN: u8 = 0;
+    const IN_PROGRESS: u8 = 1;
+    const DONE: u8 = 2;
+
+    let mut state = vec![UNSEEN; graph.color_count()];
+    for root in roots {
+        if state[root.as_usize()] != UNSEEN {
+            continue;
+        }
+        state[root.as_usize()] = IN_PROGRESS;
+        let mut stack = vec![(root, 0)];
+        while let Some(top) = stack.last_mut() {
+            let (color, child) = *top;
+            top.1 += 1;
+            match graph.contents(color).get(child) {
+                Some(edge) => match state[edge.color.as_usize()] {
+                    UNSEEN => {
+                        state[edge.color.as_usize()] = IN_PROGRESS;
+                        stack.push((edge.color, 0));
+                    }
+                    IN_PROGRESS => return Some(edge.color),
+                    _ => (),
+                },
+                None => {
+                    state[color.as_usize()] = DONE;
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Groups colors that eventually contain each other.
+fn strongly_connected(graph: &BagGraph) -> Vec<Vec<StringKey>> {
+    let colors = graph.colors().collect::<Vec<_>>();
+    let adjacency = colors
+        .iter()
+        .map(|&c| {
+            graph
+                .contents(c)
+                .iter()
+                .map(|e| e.color.as_usize())
+                .collect()
+        })
+        .collect::<Vec<_>>();
+    scc::strongly_connected(&adjacency)
+        .into_iter()
+        .map(|members| members.into_iter().map(|i| colors[i]).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+
+    const INVALID_DATA: &str = "\
+        light red bags contain 1 bright white bag, 2 muted yellow bags.\n\
+        bright white bags contain 1 shiny gold bag.\n\
+        shiny gold bags contain 1 light red bag.\n\
+        muted yellow bags contain 2 muted yellow bags, 9 faded blue bags.\n\
+        dotted black bags contain no other bags.\n\
+        dotted black bags contain 2 faded blue bags.";
+
+    #[test]
+    fn test_validate() {
+        let graph = parse_input(INVALID_DATA);
+        let problems = validate(&graph);
+
+        assert_eq!(
+            vec![
+                Problem::Cycle {
+                    colors: vec![
+                        "light red".to_owned(),
+                        "bright white".to_owned(),
+                        "shiny gold".to_owned()
+                    ],
+                    lines: vec![1, 2, 3],
+                },
+                Problem::SelfContainment {
+                    color: "muted yellow".to_owned(),
+                    lines: vec![4],
+                },
+                Problem::Undefined {
+                    color: "faded blue".to_owned(),
+                    lines: vec![4, 5, 6],
+                },
+                Problem::Duplicate {
+                    color: "dotted black".to_owned(),
+                    lines: vec![5, 6],
+                },
+            ],
+            problems
+        );
+        assert_eq!(
+            "lines 1, 2, 3: bags contain each other: light red, bright white, shiny gold",
+            problems[0].to_string()
+        );
+        assert_eq!(
+            "line 4: muted yellow bags contain themselves",
+            problems[1].to_string()
+        );
+    }
+
+    #[test]
+    fn test_malformed() {
+        let graph = parse_input(
+            "light red bags contain no other bags.\n\
+            bright white bags contain no other bags\n\
+            \n\
+            shiny gold bags hold 2 dark red bags.\n\
+            dark red bags contain two faded blue bags.\n\
+            faded blue bags contain 3 bags.\n",
+        );
+
+        assert_eq!(
+            vec![
+                "line 2: expected the rule to end with '.'",
+                "line 4: expected \"<color> bags contain ...\"",
+                "line 5: \"two\" is not a number of bags",
+                "line 6: expected \"<count> <color> bags\", found \"3 bags\"",
+            ],
+            validate(&graph)
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(validate(&graph), malformed(&graph));
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        let graph = parse_input(INVALID_DATA);
+        let key = |color| graph.try_get_key(color).unwrap();
+
+        assert_eq!(
+            Some(key("bright white")),
+            find_cycle(&graph, std::iter::once(key("bright white")))
+        );
+        assert_eq!(
+            None,
+            find_cycle(&graph, std::iter::once(key("dotted black")))
+        );
+        assert_eq!(None, find_cycle(&graph, std::iter::empty()));
+    }
+}
@@ -0,0 +1,335 @@
+use crate::instruction::Instruction;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Edge {
+    Block(usize),
+    Exit,
+}
+
+/// A run of instructions that is only entered at `start` and only branches at `end`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BasicBlock {
+    pub start: usize,
+    /// Inclusive.
+    pub end: usize,
+    pub successors: Vec<Edge>,
+}
+
+pub struct Analysis {
+    pub reachable: Vec<bool>,
+    pub blocks: Vec<BasicBlock>,
+    /// Groups of blocks that can reach each other, so execution may cycle through them.
+    pub loops: Vec<Vec<usize>>,
+    /// Instructions that jump outside of the program, with their target.
+    pub out_of_bounds: Vec<(usize, i64)>,
+    pub terminates: bool,
+    /// Instructions for which swapping `jmp` and `nop` lets the program reach its end.
+    pub terminating_flips: Vec<usize>,
+}
+
+fn is_branch(instruction: Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Jmp(_) | Instruction::Jz(_, _) | Instruction::Jnz(_, _) | Instruction::Hlt
+    )
+}
+
+/// All places execution can continue after `idx`, regardless of register values. An index equal
+/// to the program length means the program terminates.
+fn targets(instruction: Instruction, idx: usize) -> Vec<i64> {
+    let idx = idx as i64;
+    match instruction {
+        Instruction::Jmp(o) => vec![idx + o as i64],
+        Instruction::Jz(_, o) | Instruction::Jnz(_, o) => vec![idx + 1, idx + o as i64],
+        Instruction::Hlt => vec![],
+        _ => vec![idx + 1],
+    }
+}
+
+fn in_bounds(target: i64, len: usize) -> bool {
+    target >= 0 && target <= len as i64
+}
+
+/// Whether the end of the program is reachable from the first instruction, optionally with the
+/// instruction at `flip` swapped. For programs that only branch through `jmp`, this is exactly
+/// whether the program terminates.
+fn exit_reachable(instructions: &[Instruction], flip: Option<usize>) -> bool {
+    let len = instructions.len();
+    let mut visited = vec![false; len];
+    let mut stack = vec![0];
+
+    while let Some(idx) = stack.pop() {
+        if idx == len || instructions[idx] == Instruction::Hlt {
+            return true;
+        }
+        if visited[idx] {
+            continue;
+        }
+        visited[idx] = true;
+
+        let mut instruction = instructions[idx];
+        if flip == Some(idx) {
+            instruction = instruction.flip().unwrap_or(instruction);
+        }
+        for target in targets(instruction, idx) {
+            if in_bounds(target, len) {
+                stack.push(target as usize);
+            }
+        }
+    }
+
+    false
+}
+
+fn find_reachable(instructions: &[Instruction]) -> Vec<bool> {
+    let len = instructions.len();
+    let mut reachable = vec![false; len];
+    let mut stack = vec![0];
+
+    while let Some(idx) = stack.pop() {
+        if idx >= len || reachable[idx] {
+            continue;
+        }
+        reachable[idx] = true;
+        for target in targets(instructions[idx], idx) {
+            if in_bounds(target, len) {
+                stack.push(target as usize);
+            }
+        }
+    }
+
+    reachable
+}
+
+fn find_blocks(instructions: &[Instruction]) -> Vec<BasicBlock> {
+    let len = instructions.len();
+    let mut leaders = vec![false; len];
+    if len > 0 {
+        leaders[0] = true;
+    }
+    for (idx, &instruction) in instructions.iter().enumerate() {
+        if !is_branch(instruction) {
+            continue;
+        }
+        if idx + 1 < len {
+            leaders[idx + 1] = true;
+        }
+        for target in targets(instruction, idx) {
+            if target >= 0 && target < len as i64 {
+                leaders[target as usize] = true;
+            }
+        }
+    }
+
+    // maps every instruction to the block that contains it
+    let mut block_of = Vec::with_capacity(len);
+    let mut starts = vec![];
+    for (idx, &leader) in leaders.iter().enumerate() {
+        if leader {
+            starts.push(idx);
+        }
+        block_of.push(starts.len() - 1);
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(block, &start)| {
+            let end = starts.get(block + 1).map_or(len, |&next| next) - 1;
+            let successors = targets(instructions[end], end)
+                .into_iter()
+                .filter(|&t| in_bounds(t, len))
+                .map(|t| match t as usize {
+                    t if t == len => Edge::Exit,
+                    t => Edge::Block(block_of[t]),
+                })
+                .collect();
+            BasicBlock {
+                start,
+                end,
+                successors,
+            }
+        })
+        .collect()
+}
+
+impl Analysis {
+    pub fn new(instructions: &[Instruction]) -> Self {
+        let reachable = find_reachable(instructions);
+        let blocks = find_blocks(instructions);
+
+        let adjacency = blocks
+            .iter()
+            .map(|b| {
+                b.successors
+                    .iter()
+                    .filter_map(|s| match s {
+                        Edge::Block(b) => Some(*b),
+                        Edge::Exit => None,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut loops = crate::scc::strongly_connected(&adjacency)
+            .into_iter()
+            .filter(|c| c.len() > 1 || adjacency[c[0]].contains(&c[0]))
+            .collect::<Vec<_>>();
+        loops.sort();
+
+        let out_of_bounds = instructions
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, &i)| targets(i, idx).into_iter().map(move |t| (idx, t)))
+            .filter(|&(_, t)| !in_bounds(t, instructions.len()))
+            .collect();
+
+        let terminating_flips = (0..instructions.len())
+            .filter(|&idx| reachable[idx] && instructions[idx].flip().is_some())
+            .filter(|&idx| exit_reachable(instructions, Some(idx)))
+            .collect();
+
+        Self {
+            reachable,
+            blocks,
+            loops,
+            out_of_bounds,
+            terminates: exit_reachable(instructions, None),
+            terminating_flips,
+        }
+    }
+
+    pub fn unreachable(&self) -> impl Iterator<Item = usize> + '_ {
+        self.reachable
+            .iter()
+            .enumerate()
+            .filter(|(_, &r)| !r)
+            .map(|(idx, _)| idx)
+    }
+
+    pub fn report(&self, instructions: &[Instruction]) -> String {
+        let block_name = |b: &BasicBlock| format!("{}..={}", b.start, b.end);
+
+        let mut string = format!("instructions: {}\n", instructions.len());
+        string += &format!("can reach the end: {}\n", self.terminates);
+        string += &format!(
+            "unreachable instructions: {:?}\n",
+            self.unreachable().collect::<Vec<_>>()
+        );
+
+        string += &format!("basic blocks: {}\n", self.blocks.len());
+        for block in &self.blocks {
+            let successors = block
+                .successors
+                .iter()
+                .map(|s| match s {
+                    Edge::Block(b) => block_name(&self.blocks[*b]),
+                    Edge::Exit => "exit".to_owned(),
+                })
+                .collect::<Vec<_>>();
+            string += &format!("  {} -> [{}]\n", block_name(block), successors.join(", "));
+        }
+
+        string += &format!("loops: {}\n", self.loops.len());
+        for blocks in &self.loops {
+            let names = blocks
+                .iter()
+                .map(|&b| block_name(&self.blocks[b]))
+                .collect::<Vec<_>>();
+            string += &format!("  {}\n", names.join(", "));
+        }
+
+        string += &format!("out of bounds jumps: {}\n", self.out_of_bounds.len());
+        for (idx, target) in &self.out_of_bounds {
+            string += &format!("  {}: {} jumps to {}\n", idx, instructions[*idx], target);
+        }
+
+        string += &format!("terminating flips: {}\n", self.terminating_flips.len());
+        for &idx in &self.terminating_flips {
+            let instruction = instructions[idx];
+            string += &format!(
+                "  {}: {} -> {}\n",
+                idx,
+                instruction,
+                instruction.flip().unwrap()
+            );
+        }
+
+        string
+    }
+
+    /// Renders the control flow graph of basic blocks in Graphviz DOT format.
+    pub fn to_dot(&self, instructions: &[Instruction]) -> String {
+        let mut string = String::from("digraph cfg {\n    node [shape=box, fontname=monospace];\n");
+        for (idx, block) in self.blocks.iter().enumerate() {
+            let mut label = String::new();
+            for (i, instruction) in instructions[block.start..=block.end].iter().enumerate() {
+                label += &format!("{}: {}\\l", block.start + i, instruction);
+            }
+            let style = if self.reachable[block.start] {
+                ""
+            } else {
+                ", style=dashed"
+            };
+            string += &format!("    b{} [label=\"{}\"{}];\n", idx, label, style);
+        }
+        string += "    exit [shape=doublecircle];\n";
+
+        for (idx, block) in self.blocks.iter().enumerate() {
+            for successor in &block.successors {
+                match successor {
+                    Edge::Block(b) => string += &format!("    b{} -> b{};\n", idx, b),
+                    Edge::Exit => string += &format!("    b{} -> exit;\n", idx),
+                }
+            }
+        }
+        string += "}\n";
+        string
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_input, tests::INPUT};
+
+    #[test]
+    fn test_analysis() {
+        let instructions = parse_input(INPUT).unwrap();
+        let analysis = Analysis::new(&instructions);
+
+        assert_eq!(vec![5, 8], analysis.unreachable().collect::<Vec<_>>());
+        assert!(!analysis.terminates);
+        assert_eq!(vec![7], analysis.terminating_flips);
+
+        let ranges = analysis
+            .blocks
+            .iter()
+            .map(|b| (b.start, b.end))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(0, 0), (1, 2), (3, 4), (5, 5), (6, 7), (8, 8)], ranges);
+        assert_eq!(vec![Edge::Block(1)], analysis.blocks[0].successors);
+        assert_eq!(vec![Edge::Exit], analysis.blocks[5].successors);
+
+        // block 0 only enters the loop, it is not part of it
+        assert_eq!(vec![vec![1, 2, 4]], analysis.loops);
+    }
+
+    #[test]
+    fn test_out_of_bounds() {
+        let instructions = parse_input("nop +0\njmp -5\njmp +1").unwrap();
+        let analysis = Analysis::new(&instructions);
+
+        assert_eq!(vec![(1, -4)], analysis.out_of_bounds);
+        assert!(!analysis.terminates);
+        assert_eq!(vec![1], analysis.terminating_flips);
+    }
+
+    #[test]
+    fn test_dot() {
+        let instructions = parse_input("acc +1\njmp -1").unwrap();
+        let dot = Analysis::new(&instructions).to_dot(&instructions);
+
+        assert!(dot.contains("b0 [label=\"0: acc +1\\l1: jmp -1\\l\"];"));
+        assert!(dot.contains("b0 -> b0;"));
+    }
+}
@@ -0,0 +1,204 @@
+use std::{collections::HashMap, fmt};
+
+use crate::instruction::{Instruction, ParseError};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AssembleError {
+    pub line: usize,
+    pub kind: AssembleErrorKind,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum AssembleErrorKind {
+    Parse(ParseError),
+    InvalidLabel(String),
+    DuplicateLabel(String),
+    UnknownLabel(String),
+    OffsetOutOfRange(String),
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            AssembleErrorKind::Parse(e) => write!(f, "{}", e),
+            AssembleErrorKind::InvalidLabel(l) => write!(f, "invalid label '{}'", l),
+            AssembleErrorKind::DuplicateLabel(l) => write!(f, "label '{}' is defined twice", l),
+            AssembleErrorKind::UnknownLabel(l) => write!(f, "unknown label '{}'", l),
+            AssembleErrorKind::OffsetOutOfRange(l) => {
+                write!(f, "label '{}' is too far away to jump to", l)
+            }
+        }
+    }
+}
+
+/// Splits a source line into an optional label and the remaining instruction text.
+/// Comments start with `#` or `;` and run until the end of the line.
+fn split_line(line: &str) -> (Option<&str>, &str) {
+    let line = match line.find(['#', ';']) {
+        Some(idx) => &line[..idx],
+        None => line,
+    };
+
+    match line.find(':') {
+        Some(idx) => (Some(line[..idx].trim()), line[idx + 1..].trim()),
+        None => (None, line.trim()),
+    }
+}
+
+fn is_valid_label(label: &str) -> bool {
+    let mut chars = label.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Assembles source text into instructions. Besides the puzzle syntax, this accepts comments
+/// and labels, which can be used in place of the offset of `jmp`, `nop`, `jz` and `jnz`.
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, AssembleError> {
+    // first pass: find the instruction index each label points to
+    let mut labels = HashMap::new();
+    let mut index = 0;
+    for (line_idx, line) in source.lines().enumerate() {
+        let (label, rest) = split_line(line);
+        if let Some(label) = label {
+            let error = |kind| AssembleError {
+                line: line_idx + 1,
+                kind,
+            };
+            if !is_valid_label(label) {
+                return Err(error(AssembleErrorKind::InvalidLabel(label.to_owned())));
+            }
+            if labels.insert(label, index).is_some() {
+                return Err(error(AssembleErrorKind::DuplicateLabel(label.to_owned())));
+            }
+        }
+        if !rest.is_empty() {
+            index += 1;
+        }
+    }
+
+    // second pass: parse instructions, resolving labels to relative offsets
+    let mut instructions = Vec::with_capacity(index);
+    for (line_idx, line) in source.lines().enumerate() {
+        let error = |kind| AssembleError {
+            line: line_idx + 1,
+            kind,
+        };
+
+        let (_, rest) = split_line(line);
+        let mut split = rest.split_whitespace();
+        let opcode = match split.next() {
+            Some(op) => op,
+            None => continue,
+        };
+        let mut operands = split.map(str::to_owned).collect::<Vec<_>>();
+
+        if Instruction::takes_offset(opcode) {
+            if let Some(last) = operands.last_mut().filter(|l| is_valid_label(l)) {
+                let target = *labels
+                    .get(last.as_str())
+                    .ok_or_else(|| error(AssembleErrorKind::UnknownLabel(last.clone())))?;
+                let offset = target as i64 - instructions.len() as i64;
+                if offset < i16::MIN as i64 || offset > i16::MAX as i64 {
+                    return Err(error(AssembleErrorKind::OffsetOutOfRange(last.clone())));
+                }
+                *last = offset.to_string();
+            }
+        }
+
+        let operands = operands.iter().map(String::as_str).collect::<Vec<_>>();
+        let instruction = Instruction::from_parts(opcode, &operands)
+            .map_err(|e| error(AssembleErrorKind::Parse(e)))?;
+        instructions.push(instruction);
+    }
+
+    Ok(instructions)
+}
+
+/// Turns instructions back into source text that [`assemble`] accepts.
+pub fn disassemble(instructions: &[Instruction]) -> String {
+    let mut string = String::new();
+    for instruction in instructions {
+        string += &instruction.to_string();
+        string.push('\n');
+    }
+    string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::Register;
+
+    #[test]
+    fn test_labels_and_comments() {
+        let source = "\
+            # count down from 3\n\
+            set r1 +3\n\
+            set r2 -1\n\
+            loop: out r1 ; print the counter\n\
+            add r1 r2\n\
+            jnz r1 loop\n\
+            hlt";
+
+        let r1 = Register::parse("r1").unwrap();
+        let r2 = Register::parse("r2").unwrap();
+        assert_eq!(
+            Ok(vec![
+                Instruction::Set(r1, 3),
+                Instruction::Set(r2, -1),
+                Instruction::Out(r1),
+                Instruction::Add(r1, r2),
+                Instruction::Jnz(r1, -2),
+                Instruction::Hlt,
+            ]),
+            assemble(source)
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = assemble("nop +0\njmp nowhere").unwrap_err();
+        assert_eq!(2, error.line);
+        assert_eq!(
+            AssembleErrorKind::UnknownLabel("nowhere".to_owned()),
+            error.kind
+        );
+
+        let error = assemble("a: nop +0\na: hlt").unwrap_err();
+        assert_eq!(
+            AssembleErrorKind::DuplicateLabel("a".to_owned()),
+            error.kind
+        );
+
+        let error = assemble("add r1 r9").unwrap_err();
+        assert_eq!(
+            AssembleErrorKind::Parse(ParseError::InvalidRegister("r9".to_owned())),
+            error.kind
+        );
+
+        let error = assemble("frobnicate +1").unwrap_err();
+        assert_eq!(
+            AssembleErrorKind::Parse(ParseError::UnknownOpcode("frobnicate".to_owned())),
+            error.kind
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let source = "\
+            set r3 +7\n\
+            mov acc r3\n\
+            sub acc r1\n\
+            mul r2 r3\n\
+            jz r2 +2\n\
+            acc -5\n\
+            nop +0\n\
+            jmp -7\n\
+            out acc\n\
+            hlt\n";
+
+        let assembled = assemble(source).unwrap();
+        assert_eq!(source, disassemble(&assembled));
+    }
+}
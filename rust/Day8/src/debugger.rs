@@ -0,0 +1,345 @@
+use std::{
+    collections::BTreeSet,
+    fmt,
+    io::{BufRead, Write},
+};
+
+use crate::{instruction::Register, ExitReason, Snapshot, VirtualMachine};
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Watchpoint {
+    pub register: Register,
+    /// Stop only when the register becomes this value, instead of on every change.
+    pub value: Option<i32>,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum StopReason {
+    Stepped,
+    Breakpoint(usize),
+    Watchpoint {
+        register: Register,
+        old: i32,
+        new: i32,
+    },
+    /// The instruction at this index is about to be executed for the second time.
+    Looped(usize),
+    Exited(ExitReason),
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Stepped => write!(f, "stepped"),
+            StopReason::Breakpoint(ip) => write!(f, "hit breakpoint at {}", ip),
+            StopReason::Watchpoint { register, old, new } => {
+                write!(
+                    f,
+                    "watchpoint: {} changed from {} to {}",
+                    register, old, new
+                )
+            }
+            StopReason::Looped(ip) => write!(f, "instruction {} is about to run again", ip),
+            StopReason::Exited(reason) => write!(f, "program {}", reason),
+        }
+    }
+}
+
+pub struct Debugger<'vm, 'a> {
+    vm: &'vm mut VirtualMachine<'a>,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: Vec<Watchpoint>,
+    saved: Option<Snapshot>,
+}
+
+impl<'vm, 'a> Debugger<'vm, 'a> {
+    pub fn new(vm: &'vm mut VirtualMachine<'a>) -> Self {
+        Self {
+            vm,
+            breakpoints: BTreeSet::new(),
+            watchpoints: vec![],
+            saved: None,
+        }
+    }
+
+    pub fn save(&mut self) {
+        self.saved = Some(self.vm.snapshot());
+    }
+
+    /// Goes back to the state from the last [`Self::save`], if there is one.
+    pub fn load(&mut self) -> bool {
+        match &self.saved {
+            Some(snapshot) => {
+                self.vm.restore(snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.vm.reset();
+    }
+
+    pub fn add_breakpoint(&mut self, ip: usize) {
+        self.breakpoints.insert(ip);
+    }
+
+    pub fn remove_breakpoint(&mut self, ip: usize) -> bool {
+        self.breakpoints.remove(&ip)
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.watchpoints.push(watchpoint);
+    }
+
+    pub fn clear_watchpoints(&mut self) {
+        self.watchpoints.clear();
+    }
+
+    fn check_runnable(&self) -> Option<StopReason> {
+        self.vm.exit_reason().map(StopReason::Exited)
+    }
+
+    /// Executes a single instruction, reporting a triggered watchpoint if there is one.
+    pub fn step(&mut self) -> StopReason {
+        if let Some(reason) = self.check_runnable() {
+            return reason;
+        }
+
+        let before = self.vm.registers;
+        self.vm.mark_visited();
+        self.vm.run_instruction();
+
+        for watchpoint in &self.watchpoints {
+            let old = before[watchpoint.register.as_usize()];
+            let new = self.vm.get_register(watchpoint.register);
+            let triggered = match watchpoint.value {
+                Some(value) => old != value && new == value,
+                None => old != new,
+            };
+            if triggered {
+                return StopReason::Watchpoint {
+                    register: watchpoint.register,
+                    old,
+                    new,
+                };
+            }
+        }
+
+        StopReason::Stepped
+    }
+
+    /// Runs until a breakpoint or watchpoint is hit, the program ends, or an instruction is about
+    /// to be executed a second time.
+    pub fn resume(&mut self) -> StopReason {
+        loop {
+            match self.step() {
+                StopReason::Stepped => (),
+                reason => return reason,
+            }
+
+            if let Some(reason) = self.check_runnable() {
+                return reason;
+            }
+
+            let ip = self.vm.get_ip();
+            if self.vm.is_visited(ip) {
+                // forget the visited instructions so resuming runs the loop once more
+                self.vm.forget_visited();
+                return StopReason::Looped(ip);
+            }
+            if self.breakpoints.contains(&ip) {
+                return StopReason::Breakpoint(ip);
+            }
+        }
+    }
+
+    /// Lists the instructions within `radius` of the instruction pointer.
+    pub fn context(&self, radius: usize) -> String {
+        let ip = self.vm.get_ip();
+        let len = self.vm.program_len();
+        let start = ip.saturating_sub(radius).min(len);
+        let end = ip.saturating_add(radius + 1).min(len);
+
+        let mut string = String::new();
+        for idx in start..end {
+            let marker = if idx == ip { '>' } else { ' ' };
+            let breakpoint = if self.breakpoints.contains(&idx) {
+                '*'
+            } else {
+                ' '
+            };
+            string += &format!(
+                "{}{} {:>5}  {}\n",
+                marker,
+                breakpoint,
+                idx,
+                self.vm.instruction_at(idx)
+            );
+        }
+        string
+    }
+
+    pub fn registers(&self) -> String {
+        let mut string = format!("ip {}", self.vm.get_ip());
+        for register in Register::all() {
+            string += &format!("  {} {}", register, self.vm.get_register(register));
+        }
+        string
+    }
+}
+
+const HELP: &str = "\
+commands:
+  s, step [n]       execute n instructions (default 1)
+  c, continue       run until a breakpoint, watchpoint, loop or the end
+  b, break <ip>     set a breakpoint on an instruction index
+  d, delete <ip>    remove a breakpoint
+  w, watch [reg] [value]
+                    stop when a register (default acc) changes, or becomes value
+  unwatch           remove all watchpoints
+  l, list [radius]  show instructions around the ip (default 3)
+  r, regs           show the ip and registers
+  save              remember the current state
+  load              go back to the remembered state
+  reset             restart the program
+  q, quit           exit the debugger";
+
+/// Reads debugger commands from `input` until it runs out or `quit` is given.
+pub fn run_cli(debugger: &mut Debugger, input: impl BufRead, mut output: impl Write) {
+    writeln!(output, "{}", debugger.context(3).trim_end()).unwrap();
+    for line in input.lines() {
+        let line = line.unwrap();
+        let mut split = line.split_whitespace();
+        let command = match split.next() {
+            Some(c) => c,
+            None => continue,
+        };
+        let args = split.collect::<Vec<_>>();
+        let number = |i: usize| args.get(i).and_then(|a| a.parse::<usize>().ok());
+
+        let message = match command {
+            "s" | "step" => {
+                let mut reason = StopReason::Stepped;
+                for _ in 0..number(0).unwrap_or(1) {
+                    reason = debugger.step();
+                    if reason != StopReason::Stepped {
+                        break;
+                    }
+                }
+                format!("{}\n{}", reason, debugger.context(1))
+            }
+            "c" | "continue" => format!("{}\n{}", debugger.resume(), debugger.context(1)),
+            "b" | "break" => match number(0) {
+                Some(ip) => {
+                    debugger.add_breakpoint(ip);
+                    format!("breakpoint set at {}", ip)
+                }
+                None => "usage: break <ip>".to_owned(),
+            },
+            "d" | "delete" => match number(0) {
+                Some(ip) if debugger.remove_breakpoint(ip) => format!("removed breakpoint {}", ip),
+                Some(ip) => format!("no breakpoint at {}", ip),
+                None => "usage: delete <ip>".to_owned(),
+            },
+            "w" | "watch" => {
+                let (register, value) = match args.first().map(|a| Register::parse(a)) {
+                    Some(Ok(register)) => (register, args.get(1)),
+                    _ => (Register::ACC, args.first()),
+                };
+                match value.map(|v| v.parse::<i32>()).transpose() {
+                    Ok(value) => {
+                        debugger.add_watchpoint(Watchpoint { register, value });
+                        format!("watching {}", register)
+                    }
+                    Err(_) => "usage: watch [reg] [value]".to_owned(),
+                }
+            }
+            "unwatch" => {
+                debugger.clear_watchpoints();
+                "removed all watchpoints".to_owned()
+            }
+            "l" | "list" => debugger.context(number(0).unwrap_or(3)),
+            "r" | "regs" => debugger.registers(),
+            "save" => {
+                debugger.save();
+                "saved current state".to_owned()
+            }
+            "load" if debugger.load() => debugger.context(3),
+            "load" => "nothing saved yet".to_owned(),
+            "reset" => {
+                debugger.reset();
+                debugger.context(3)
+            }
+            "q" | "quit" => return,
+            _ => HELP.to_owned(),
+        };
+        writeln!(output, "{}", message.trim_end()).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_input, tests::INPUT};
+
+    #[test]
+    fn test_breakpoints_and_loops() {
+        let instructions = parse_input(INPUT).unwrap();
+        let mut vm = VirtualMachine::new(&instructions);
+        let mut debugger = Debugger::new(&mut vm);
+
+        debugger.add_breakpoint(6);
+        assert_eq!(StopReason::Breakpoint(6), debugger.resume());
+        assert_eq!(1, debugger.vm.get_acc());
+        debugger.save();
+
+        assert_eq!(StopReason::Looped(1), debugger.resume());
+        assert_eq!(5, debugger.vm.get_acc());
+
+        // loading brings back the instructions visited before saving
+        assert!(debugger.load());
+        assert_eq!(StopReason::Looped(1), debugger.resume());
+        assert_eq!(5, debugger.vm.get_acc());
+    }
+
+    #[test]
+    fn test_watchpoints() {
+        let instructions = parse_input(INPUT).unwrap();
+        let mut vm = VirtualMachine::new(&instructions);
+        let mut debugger = Debugger::new(&mut vm);
+
+        debugger.add_watchpoint(Watchpoint {
+            register: Register::ACC,
+            value: Some(5),
+        });
+        assert_eq!(
+            StopReason::Watchpoint {
+                register: Register::ACC,
+                old: 2,
+                new: 5,
+            },
+            debugger.resume()
+        );
+        assert_eq!(4, debugger.vm.get_ip());
+    }
+
+    #[test]
+    fn test_cli() {
+        let instructions = parse_input(INPUT).unwrap();
+        let mut vm = VirtualMachine::new(&instructions);
+        let mut debugger = Debugger::new(&mut vm);
+
+        let mut output = vec![];
+        run_cli(
+            &mut debugger,
+            "step 3\nsave\nstep 2\nload\nregs\nquit\nregs".as_bytes(),
+            &mut output,
+        );
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains(">      6  acc +1"));
+        assert!(output.ends_with("ip 6  acc 1  r1 0  r2 0  r3 0\n"));
+    }
+}
@@ -0,0 +1,172 @@
+use std::fmt;
+
+pub const REGISTER_COUNT: usize = 4;
+
+/// A general purpose register. Register 0 is the accumulator used by `acc`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Register(u8);
+
+impl Register {
+    pub const ACC: Register = Register(0);
+
+    pub fn parse(string: &str) -> Result<Self, ParseError> {
+        if string == "acc" {
+            return Ok(Self::ACC);
+        }
+
+        string
+            .strip_prefix('r')
+            .and_then(|idx| idx.parse::<u8>().ok())
+            .filter(|&idx| idx != 0 && (idx as usize) < REGISTER_COUNT)
+            .map(Register)
+            .ok_or_else(|| ParseError::InvalidRegister(string.to_owned()))
+    }
+
+    pub fn as_usize(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn all() -> impl Iterator<Item = Register> {
+        (0..REGISTER_COUNT as u8).map(Register)
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            0 => write!(f, "acc"),
+            i => write!(f, "r{}", i),
+        }
+    }
+}
+
+/// Jump offsets are relative to the instruction they appear in.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Instruction {
+    Acc(i16),
+    Jmp(i16),
+    Nop(i16),
+    Set(Register, i16),
+    Mov(Register, Register),
+    Add(Register, Register),
+    Sub(Register, Register),
+    Mul(Register, Register),
+    Jz(Register, i16),
+    Jnz(Register, i16),
+    Out(Register),
+    Hlt,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ParseError {
+    Empty,
+    UnknownOpcode(String),
+    InvalidRegister(String),
+    InvalidNumber(String),
+    OperandCount {
+        opcode: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "empty instruction"),
+            ParseError::UnknownOpcode(op) => write!(f, "unknown opcode '{}'", op),
+            ParseError::InvalidRegister(r) => write!(f, "invalid register '{}'", r),
+            ParseError::InvalidNumber(n) => write!(f, "invalid number '{}'", n),
+            ParseError::OperandCount {
+                opcode,
+                expected,
+                found,
+            } => write!(
+                f,
+                "'{}' takes {} operand(s), found {}",
+                opcode, expected, found
+            ),
+        }
+    }
+}
+
+impl Instruction {
+    pub fn parse(string: &str) -> Result<Self, ParseError> {
+        let mut split = string.split_whitespace();
+        let opcode = split.next().ok_or(ParseError::Empty)?;
+        let operands = split.collect::<Vec<_>>();
+        Self::from_parts(opcode, &operands)
+    }
+
+    pub fn from_parts(opcode: &str, operands: &[&str]) -> Result<Self, ParseError> {
+        let expected = match opcode {
+            "hlt" => 0,
+            "acc" | "jmp" | "nop" | "out" => 1,
+            "set" | "mov" | "add" | "sub" | "mul" | "jz" | "jnz" => 2,
+            _ => return Err(ParseError::UnknownOpcode(opcode.to_owned())),
+        };
+        if operands.len() != expected {
+            return Err(ParseError::OperandCount {
+                opcode: opcode.to_owned(),
+                expected,
+                found: operands.len(),
+            });
+        }
+
+        let reg = |i: usize| Register::parse(operands[i]);
+        let num = |i: usize| {
+            operands[i]
+                .parse::<i16>()
+                .map_err(|_| ParseError::InvalidNumber(operands[i].to_owned()))
+        };
+
+        Ok(match opcode {
+            "acc" => Instruction::Acc(num(0)?),
+            "jmp" => Instruction::Jmp(num(0)?),
+            "nop" => Instruction::Nop(num(0)?),
+            "set" => Instruction::Set(reg(0)?, num(1)?),
+            "mov" => Instruction::Mov(reg(0)?, reg(1)?),
+            "add" => Instruction::Add(reg(0)?, reg(1)?),
+            "sub" => Instruction::Sub(reg(0)?, reg(1)?),
+            "mul" => Instruction::Mul(reg(0)?, reg(1)?),
+            "jz" => Instruction::Jz(reg(0)?, num(1)?),
+            "jnz" => Instruction::Jnz(reg(0)?, num(1)?),
+            "out" => Instruction::Out(reg(0)?),
+            "hlt" => Instruction::Hlt,
+            _ => unreachable!(),
+        })
+    }
+
+    /// Whether the last operand of this opcode is a jump offset, and may be given as a label.
+    pub fn takes_offset(opcode: &str) -> bool {
+        matches!(opcode, "jmp" | "nop" | "jz" | "jnz")
+    }
+
+    /// Swaps `jmp` and `nop`, which is the corruption part 2 is about.
+    pub fn flip(self) -> Option<Self> {
+        match self {
+            Instruction::Jmp(op) => Some(Instruction::Nop(op)),
+            Instruction::Nop(op) => Some(Instruction::Jmp(op)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Acc(i) => write!(f, "acc {:+}", i),
+            Instruction::Jmp(i) => write!(f, "jmp {:+}", i),
+            Instruction::Nop(i) => write!(f, "nop {:+}", i),
+            Instruction::Set(r, i) => write!(f, "set {} {:+}", r, i),
+            Instruction::Mov(a, b) => write!(f, "mov {} {}", a, b),
+            Instruction::Add(a, b) => write!(f, "add {} {}", a, b),
+            Instruction::Sub(a, b) => write!(f, "sub {} {}", a, b),
+            Instruction::Mul(a, b) => write!(f, "mul {} {}", a, b),
+            Instruction::Jz(r, i) => write!(f, "jz {} {:+}", r, i),
+            Instruction::Jnz(r, i) => write!(f, "jnz {} {:+}", r, i),
+            Instruction::Out(r) => write!(f, "out {}", r),
+            Instruction::Hlt => write!(f, "hlt"),
+        }
+    }
+}
@@ -1,98 +1,331 @@
+mod analysis;
+mod assembler;
+mod debugger;
+mod instruction;
+#[path = "../../scc.rs"]
+mod scc;
+mod trace;
+
+use instruction::{Instruction, Register, REGISTER_COUNT};
+use trace::{Trace, TraceEntry};
+
 include!("../../helpers.rs");
 
-const BUFFER_SIZE: usize = 1024;
+const MAX_STEPS: usize = 10_000_000;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+enum ExitReason {
+    /// The program halted or ran the instruction right after the last one.
+    Terminated,
+    Looped,
+    /// The instruction at `ip` tried to jump to `target`, which is outside of the program.
+    JumpedOutOfBounds {
+        ip: usize,
+        target: i64,
+    },
+}
+
+impl std::fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExitReason::Terminated => write!(f, "terminated"),
+            ExitReason::Looped => write!(f, "looped"),
+            ExitReason::JumpedOutOfBounds { ip, target } => {
+                write!(f, "instruction {} jumped out of bounds to {}", ip, target)
+            }
+        }
+    }
+}
+
+/// Everything needed to put a [`VirtualMachine`] back into an earlier state, except the program
+/// it runs. Patched instructions are stored as a list of changes, so this stays cheap to take.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Snapshot {
+    registers: [i32; REGISTER_COUNT],
+    instruction_pointer: usize,
+    halted: bool,
+    jump_target_out_of_bounds: Option<i64>,
+    output_len: usize,
+    visited: Vec<bool>,
+    patches: Vec<(usize, Instruction)>,
+}
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 struct VirtualMachine<'a> {
-    accumulator: i32,
-    instructions: &'a mut [Instruction],
+    registers: [i32; REGISTER_COUNT],
+    instructions: &'a [Instruction],
+    /// Instructions that replace those in `instructions`, so the program itself is never mutated.
+    patches: Vec<(usize, Instruction)>,
     instruction_pointer: usize,
+    halted: bool,
+    jump_target_out_of_bounds: Option<i64>,
+    output: Vec<i32>,
+    trace: Option<Trace>,
+    /// Instructions run by [`Self::run_until_recursion`] or marked by [`Self::mark_visited`] so
+    /// far.
+    visited: Vec<bool>,
 }
 
 impl<'a> VirtualMachine<'a> {
-    pub fn new(instructions: &'a mut [Instruction]) -> Self {
+    pub fn new(instructions: &'a [Instruction]) -> Self {
         Self {
             instructions,
             ..Default::default()
         }
     }
 
+    /// Resets the machine to the start of the program, keeping any patches.
     pub fn reset(&mut self) {
-        self.accumulator = 0;
+        self.registers = [0; REGISTER_COUNT];
         self.instruction_pointer = 0;
+        self.halted = false;
+        self.jump_target_out_of_bounds = None;
+        self.output.clear();
+        self.visited.clear();
+        if let Some(trace) = &mut self.trace {
+            trace.entries.clear();
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            registers: self.registers,
+            instruction_pointer: self.instruction_pointer,
+            halted: self.halted,
+            jump_target_out_of_bounds: self.jump_target_out_of_bounds,
+            output_len: self.output.len(),
+            visited: self.visited.clone(),
+            patches: self.patches.clone(),
+        }
+    }
+
+    /// Goes back to the state of a snapshot taken earlier from this machine. Output produced
+    /// since then is discarded, and so are trace entries.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.registers = snapshot.registers;
+        self.instruction_pointer = snapshot.instruction_pointer;
+        self.halted = snapshot.halted;
+        self.jump_target_out_of_bounds = snapshot.jump_target_out_of_bounds;
+        self.output.truncate(snapshot.output_len);
+        self.visited.clone_from(&snapshot.visited);
+        self.patches.clone_from(&snapshot.patches);
+        if let Some(trace) = &mut self.trace {
+            trace.entries.clear();
+        }
+    }
+
+    /// Creates an independent copy that shares the (unpatched) program with this machine.
+    pub fn fork(&self) -> Self {
+        self.clone()
+    }
+
+    pub fn patch(&mut self, index: usize, instruction: Instruction) {
+        match self.patches.iter_mut().find(|(i, _)| *i == index) {
+            Some(patch) => patch.1 = instruction,
+            None => self.patches.push((index, instruction)),
+        }
+    }
+
+    pub fn instruction_at(&self, index: usize) -> Instruction {
+        self.patches
+            .iter()
+            .find(|(i, _)| *i == index)
+            .map_or(self.instructions[index], |(_, instruction)| *instruction)
+    }
+
+    pub fn program_len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    /// Starts recording every executed instruction, see [`Self::take_trace`].
+    pub fn enable_tracing(&mut self) {
+        self.trace = Some(Trace::default());
+    }
+
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        let mut trace = self.trace.take()?;
+        let looped = self.visited.get(self.instruction_pointer) == Some(&true);
+        trace.end_ip = Some(self.instruction_pointer);
+        trace.exit = self.exit_reason().or(if looped {
+            Some(ExitReason::Looped)
+        } else {
+            None
+        });
+        Some(trace)
     }
 
     pub fn run_instruction(&mut self) {
-        let instruction = self.instructions[self.instruction_pointer];
-        let mut instruction_jump = None;
+        let instruction = self.instruction_at(self.instruction_pointer);
+        let acc_before = self.get_acc();
+        let mut instruction_jump: Option<i16> = None;
         match instruction {
             Instruction::Acc(i) => {
-                self.accumulator += i as i32;
+                let acc = &mut self.registers[Register::ACC.as_usize()];
+                *acc = acc.wrapping_add(i as i32);
             }
             Instruction::Jmp(i) => {
-                instruction_jump = Some(i as i32);
+                instruction_jump = Some(i);
             }
             Instruction::Nop(_) => (),
+            Instruction::Set(r, i) => self.registers[r.as_usize()] = i as i32,
+            Instruction::Mov(a, b) => self.registers[a.as_usize()] = self.get_register(b),
+            Instruction::Add(a, b) => {
+                self.registers[a.as_usize()] =
+                    self.get_register(a).wrapping_add(self.get_register(b))
+            }
+            Instruction::Sub(a, b) => {
+                self.registers[a.as_usize()] =
+                    self.get_register(a).wrapping_sub(self.get_register(b))
+            }
+            Instruction::Mul(a, b) => {
+                self.registers[a.as_usize()] =
+                    self.get_register(a).wrapping_mul(self.get_register(b))
+            }
+            Instruction::Jz(r, i) => {
+                if self.get_register(r) == 0 {
+                    instruction_jump = Some(i);
+                }
+            }
+            Instruction::Jnz(r, i) => {
+                if self.get_register(r) != 0 {
+                    instruction_jump = Some(i);
+                }
+            }
+            Instruction::Out(r) => self.output.push(self.get_register(r)),
+            Instruction::Hlt => {
+                self.halted = true;
+                instruction_jump = Some(0);
+            }
+        }
+
+        if let Some(trace) = &mut self.trace {
+            trace.entries.push(TraceEntry {
+                ip: self.instruction_pointer,
+                instruction,
+                acc_before,
+                acc_after: self.registers[Register::ACC.as_usize()],
+            });
         }
 
-        self.instruction_pointer = (self.instruction_pointer as i32
-            + match instruction_jump {
-                Some(j) => j,
-                None => 1,
-            }) as usize;
+        let target = self.instruction_pointer as i64 + instruction_jump.unwrap_or(1) as i64;
+        if target < 0 || target > self.instructions.len() as i64 {
+            self.jump_target_out_of_bounds = Some(target);
+        } else {
+            self.instruction_pointer = target as usize;
+        }
     }
 
     pub fn get_ip(&self) -> usize {
         self.instruction_pointer
     }
     pub fn get_acc(&self) -> i32 {
-        self.accumulator
+        self.get_register(Register::ACC)
+    }
+    pub fn get_register(&self, register: Register) -> i32 {
+        self.registers[register.as_usize()]
+    }
+    pub fn get_output(&self) -> &[i32] {
+        &self.output
     }
 
-    pub fn run_until_recursion(&mut self) -> bool {
-        let mut recursion_lookup = [false; BUFFER_SIZE];
+    /// Whether the program can no longer continue. This does not detect loops.
+    pub fn exit_reason(&self) -> Option<ExitReason> {
+        if let Some(target) = self.jump_target_out_of_bounds {
+            Some(ExitReason::JumpedOutOfBounds {
+                ip: self.instruction_pointer,
+                target,
+            })
+        } else if self.halted || self.instruction_pointer == self.instructions.len() {
+            Some(ExitReason::Terminated)
+        } else {
+            None
+        }
+    }
 
-        loop {
-            let ip = self.get_ip();
-            if ip == self.instructions.len() {
-                return true;
-            }
-            if recursion_lookup[ip] {
-                return false;
+    /// Runs until the program exits, giving up after `max_steps`. Unlike
+    /// [`Self::run_until_recursion`], this allows programs to loop over the same instructions.
+    pub fn run(&mut self, max_steps: usize) -> Option<ExitReason> {
+        for _ in 0..max_steps {
+            if let Some(reason) = self.exit_reason() {
+                return Some(reason);
             }
-            recursion_lookup[ip] = true;
             self.run_instruction();
         }
+        self.exit_reason()
     }
-}
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-enum Instruction {
-    Acc(i16),
-    Jmp(i16),
-    Nop(i16),
-}
+    /// Runs a single instruction, unless the program exited or the instruction already ran
+    /// before.
+    pub fn step_until_recursion(&mut self) -> Option<ExitReason> {
+        if let Some(reason) = self.exit_reason() {
+            return Some(reason);
+        }
+
+        if self.mark_visited() {
+            return Some(ExitReason::Looped);
+        }
+        self.run_instruction();
+        None
+    }
+
+    /// Marks the instruction at the instruction pointer as run, returning whether it already
+    /// was. The program must not have exited.
+    pub fn mark_visited(&mut self) -> bool {
+        if self.visited.len() < self.instructions.len() {
+            self.visited.resize(self.instructions.len(), false);
+        }
+        let ip = self.get_ip();
+        std::mem::replace(&mut self.visited[ip], true)
+    }
+
+    pub fn is_visited(&self, ip: usize) -> bool {
+        self.visited.get(ip) == Some(&true)
+    }
 
-impl Instruction {
-    pub fn parse(string: &str) -> Self {
-        let mut split = string.split(' ');
+    /// Forgets which instructions ran, so the next one to run again doesn't count as a loop.
+    pub fn forget_visited(&mut self) {
+        self.visited.clear();
+    }
 
-        let opcode = split.next();
-        let operand = split.next().unwrap().parse().unwrap();
-        match opcode {
-            Some("nop") => Instruction::Nop(operand),
-            Some("acc") => Instruction::Acc(operand),
-            Some("jmp") => Instruction::Jmp(operand),
-            _ => panic!("Unknown opcode"),
+    /// Runs until the program exits or is about to run an instruction a second time. Instructions
+    /// visited before a snapshot or fork still count.
+    pub fn run_until_recursion(&mut self) -> ExitReason {
+        loop {
+            if let Some(reason) = self.step_until_recursion() {
+                return reason;
+            }
         }
     }
 }
 
 fn main() {
-    let (stdin, time_reading) = time(|| read_stdin());
-    let (mut input, time_parsing) = time(|| parse_input(&stdin));
-    let (solution_1, time_solving_1) = time(|| solve_1(&mut input));
-    let (solution_2, time_solving_2) = time(|| solve_2(&mut input));
+    match std::env::args().nth(1).as_deref() {
+        None => run_challenge(),
+        Some("run") => run_program(),
+        Some("disasm") => print!("{}", assembler::disassemble(&assemble_stdin())),
+        Some("debug") => debug_program(),
+        Some("trace") => trace_program(),
+        Some("replay") => replay_trace(),
+        Some("analyze") => analyze_program(),
+        Some(command) => {
+            eprintln!(
+                "unknown command '{}', expected 'run', 'disasm', 'debug', 'trace', 'replay' or 'analyze'",
+                command
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run_challenge() {
+    let (stdin, time_reading) = time(read_stdin);
+    let (input, time_parsing) = time(|| parse_input(&stdin));
+    let input = input.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    let (solution_1, time_solving_1) = time(|| solve_1(&input));
+    let (solution_2, time_solving_2) = time(|| solve_2(&input));
 
     println!("solution 1: {}", solution_1);
     println!("solution 1: {}", solution_2);
@@ -102,43 +335,164 @@ fn main() {
     println!("took {:?} to solve 2", time_solving_2);
 }
 
-fn solve_1(instructions: &mut [Instruction]) -> i32 {
-    let mut vm = VirtualMachine::new(instructions);
-    let b = vm.run_until_recursion();
-    assert!(!b);
-    vm.get_acc()
+fn run_program() {
+    let instructions = assemble_stdin();
+    let mut vm = VirtualMachine::new(&instructions);
+    let reason = vm.run(MAX_STEPS);
+
+    for value in vm.get_output() {
+        println!("{}", value);
+    }
+    if let Some(reason) = reason {
+        eprintln!("{} with acc {}", reason, vm.get_acc());
+    } else {
+        eprintln!(
+            "still running after {} steps, at ip {} with acc {}",
+            MAX_STEPS,
+            vm.get_ip(),
+            vm.get_acc()
+        );
+    }
 }
 
-fn solve_2(instructions: &mut [Instruction]) -> i32 {
-    let mut vm = VirtualMachine::new(instructions);
-    for i in 0..vm.instructions.len() {
-        vm.instructions[i] = match vm.instructions[i] {
-            Instruction::Acc(_) => continue,
-            Instruction::Jmp(op) => Instruction::Nop(op),
-            Instruction::Nop(op) => Instruction::Jmp(op),
-        };
+fn debug_program() {
+    let path = match std::env::args().nth(2) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: day8 debug <program>, with debugger commands on stdin");
+            std::process::exit(1);
+        }
+    };
+    let source = read_file_or_exit(&path);
+
+    let instructions = assemble_or_exit(&source);
+    let mut vm = VirtualMachine::new(&instructions);
+    let mut debugger = debugger::Debugger::new(&mut vm);
+    let stdin = std::io::stdin();
+    debugger::run_cli(&mut debugger, stdin.lock(), std::io::stdout());
+}
+
+fn trace_program() {
+    let instructions = assemble_stdin();
+    let mut vm = VirtualMachine::new(&instructions);
+    vm.enable_tracing();
+    let reason = vm.run_until_recursion();
+    let trace = vm.take_trace().unwrap();
+
+    print!("{}", trace.to_json_lines());
+    match trace.find_cycle() {
+        Some(cycle) => eprintln!(
+            "looped: instruction {} was first run at step {}, cycle of {} steps is {:?}",
+            cycle.entry_ip,
+            cycle.entry_step,
+            cycle.ips.len(),
+            cycle.ips
+        ),
+        None => eprintln!("{} after {} steps", reason, trace.entries.len()),
+    }
+}
 
-        if vm.run_until_recursion() {
-            return vm.get_acc();
+fn replay_trace() {
+    let args = std::env::args().collect::<Vec<_>>();
+    let step = args.get(3).and_then(|s| s.parse::<usize>().ok());
+    let (path, step) = match (args.get(2), step) {
+        (Some(path), Some(step)) => (path, step),
+        _ => {
+            eprintln!("usage: day8 replay <trace.jsonl> <step>");
+            std::process::exit(1);
         }
+    };
 
-        vm.reset();
+    let trace = match Trace::parse_json_lines(&read_file_or_exit(path)) {
+        Ok(trace) => trace,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    match trace.replay(step, 5) {
+        Some(replayed) => print!("{}", replayed),
+        None => {
+            eprintln!("trace only has {} steps", trace.entries.len());
+            std::process::exit(1);
+        }
+    }
+}
+
+fn analyze_program() {
+    let instructions = assemble_stdin();
+    let analysis = analysis::Analysis::new(&instructions);
+    if std::env::args().nth(2).as_deref() == Some("--dot") {
+        print!("{}", analysis.to_dot(&instructions));
+    } else {
+        print!("{}", analysis.report(&instructions));
+    }
+}
+
+fn assemble_stdin() -> Vec<Instruction> {
+    assemble_or_exit(&read_stdin())
+}
+
+fn read_file_or_exit(path: &str) -> String {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
+}
 
-        vm.instructions[i] = match vm.instructions[i] {
-            Instruction::Acc(_) => continue,
-            Instruction::Jmp(op) => Instruction::Nop(op),
-            Instruction::Nop(op) => Instruction::Jmp(op),
-        };
+fn assemble_or_exit(source: &str) -> Vec<Instruction> {
+    match assembler::assemble(source) {
+        Ok(instructions) => instructions,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
     }
+}
+
+fn solve_1(instructions: &[Instruction]) -> i32 {
+    let mut vm = VirtualMachine::new(instructions);
+    let reason = vm.run_until_recursion();
+    assert_eq!(ExitReason::Looped, reason);
+    vm.get_acc()
+}
+
+fn solve_2(instructions: &[Instruction]) -> i32 {
+    // follow the original program, and at every jmp/nop it reaches, try a fork in which that
+    // instruction is flipped. The fork keeps the visited instructions, so it stops as soon as it
+    // rejoins the original path.
+    let mut vm = VirtualMachine::new(instructions);
+    loop {
+        let ip = vm.get_ip();
+        if let Some(flipped) = vm.instruction_at(ip).flip() {
+            let mut fork = vm.fork();
+            fork.patch(ip, flipped);
+            if fork.run_until_recursion() == ExitReason::Terminated {
+                return fork.get_acc();
+            }
+        }
 
-    panic!("couldnt find good mutation of the program")
+        if vm.step_until_recursion().is_some() {
+            panic!("couldnt find good mutation of the program")
+        }
+    }
 }
 
-fn parse_input(input: &str) -> Vec<Instruction> {
+/// Reads the puzzle's plain format, one instruction per line, skipping empty lines.
+fn parse_input(input: &str) -> Result<Vec<Instruction>, assembler::AssembleError> {
     input
         .split('\n')
-        .filter(|l| *l != "")
-        .map(Instruction::parse)
+        .enumerate()
+        .filter(|(_, l)| !l.is_empty())
+        .map(|(idx, l)| {
+            Instruction::parse(l).map_err(|e| assembler::AssembleError {
+                line: idx + 1,
+                kind: assembler::AssembleErrorKind::Parse(e),
+            })
+        })
         .collect()
 }
 
@@ -146,7 +500,7 @@ fn parse_input(input: &str) -> Vec<Instruction> {
 mod tests {
     use crate::*;
 
-    const INPUT: &str = "\
+    pub(crate) const INPUT: &str = "\
         nop +0\n\
         acc +1\n\
         jmp +4\n\
@@ -159,7 +513,7 @@ mod tests {
 
     #[test]
     fn test_parsing() {
-        let parsed = parse_input(INPUT);
+        let parsed = parse_input(INPUT).unwrap();
 
         let expected = vec![
             Instruction::Nop(0),
@@ -177,17 +531,120 @@ mod tests {
         for i in 0..parsed.len() {
             assert_eq!(expected[i], parsed[i]);
         }
+
+        let error = parse_input("nop +0\n\nbogus +1\n").unwrap_err();
+        assert_eq!(3, error.line);
+        assert!(matches!(error.kind, assembler::AssembleErrorKind::Parse(_)));
+    }
+
+    #[test]
+    fn test_custom_program() {
+        let program = assembler::assemble(
+            "\
+            set r1 +5\n\
+            set r2 +1\n\
+            set r3 -1\n\
+            loop: mul r2 r1\n\
+            add r1 r3\n\
+            jnz r1 loop\n\
+            out r2\n\
+            hlt\n\
+            out r1",
+        )
+        .unwrap();
+
+        let mut vm = VirtualMachine::new(&program);
+        assert_eq!(Some(ExitReason::Terminated), vm.run(1000));
+        assert_eq!(&[120], vm.get_output());
+    }
+
+    #[test]
+    fn test_exit_reasons() {
+        let program = parse_input("nop +0\njmp -2").unwrap();
+        let mut vm = VirtualMachine::new(&program);
+        assert_eq!(
+            ExitReason::JumpedOutOfBounds { ip: 1, target: -1 },
+            vm.run_until_recursion()
+        );
+
+        let program = parse_input("jmp +3\nnop +0").unwrap();
+        let mut vm = VirtualMachine::new(&program);
+        assert_eq!(
+            ExitReason::JumpedOutOfBounds { ip: 0, target: 3 },
+            vm.run_until_recursion()
+        );
+
+        let program = parse_input("jmp +2\nnop +0").unwrap();
+        let mut vm = VirtualMachine::new(&program);
+        assert_eq!(ExitReason::Terminated, vm.run_until_recursion());
+    }
+
+    #[test]
+    fn test_long_program() {
+        // loops back from the very end of a program longer than the old fixed buffer
+        let mut program = vec![Instruction::Acc(1); 5000];
+        program.push(Instruction::Jmp(-5000));
+        let mut vm = VirtualMachine::new(&program);
+        assert_eq!(ExitReason::Looped, vm.run_until_recursion());
+        assert_eq!(5000, vm.get_acc());
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let instructions = parse_input(INPUT).unwrap();
+        let mut vm = VirtualMachine::new(&instructions);
+        vm.run_instruction();
+        vm.run_instruction();
+        let snapshot = vm.snapshot();
+
+        vm.patch(2, Instruction::Nop(4));
+        assert_eq!(ExitReason::Looped, vm.run_until_recursion());
+        assert_eq!(Instruction::Nop(4), vm.instruction_at(2));
+
+        vm.restore(&snapshot);
+        assert_eq!((2, 1), (vm.get_ip(), vm.get_acc()));
+        assert_eq!(Instruction::Jmp(4), vm.instruction_at(2));
+        assert_eq!(Instruction::Jmp(4), instructions[2]);
+    }
+
+    #[test]
+    fn test_parallel_forks() {
+        let instructions = parse_input(INPUT).unwrap();
+        let vm = VirtualMachine::new(&instructions);
+
+        let results = std::thread::scope(|scope| {
+            let handles = (0..instructions.len())
+                .filter_map(|i| instructions[i].flip().map(|f| (i, f)))
+                .map(|(i, flipped)| {
+                    let mut fork = vm.fork();
+                    scope.spawn(move || {
+                        fork.patch(i, flipped);
+                        (fork.run_until_recursion(), fork.get_acc())
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        let terminated = results
+            .iter()
+            .filter(|(reason, _)| *reason == ExitReason::Terminated)
+            .collect::<Vec<_>>();
+        assert_eq!(vec![&(ExitReason::Terminated, 8)], terminated);
     }
 
     #[test]
     fn test_solution_1() {
-        let mut parsed = parse_input(INPUT);
-        assert_eq!(5, solve_1(&mut parsed));
+        let parsed = parse_input(INPUT).unwrap();
+        assert_eq!(5, solve_1(&parsed));
     }
 
     #[test]
     fn test_solution_2() {
-        let mut parsed = parse_input(INPUT);
-        assert_eq!(8, solve_2(&mut parsed));
+        let parsed = parse_input(INPUT).unwrap();
+        assert_eq!(8, solve_2(&parsed));
     }
 }
@@ -0,0 +1,254 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{instruction::Instruction, ExitReason};
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct TraceEntry {
+    pub ip: usize,
+    pub instruction: Instruction,
+    pub acc_before: i32,
+    pub acc_after: i32,
+}
+
+/// The first instruction that was executed twice, and the steps that lead back to it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Cycle {
+    /// The step at which the loop entry was first executed.
+    pub entry_step: usize,
+    pub entry_ip: usize,
+    /// The instruction indices that make up one iteration, starting at the loop entry.
+    pub ips: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TraceParseError {
+    pub line: usize,
+}
+
+impl fmt::Display for TraceParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: invalid trace entry", self.line)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Trace {
+    pub entries: Vec<TraceEntry>,
+    /// Where the instruction pointer was when recording stopped.
+    pub end_ip: Option<usize>,
+    /// Why recording stopped, or `None` if the program could have kept running.
+    pub exit: Option<ExitReason>,
+}
+
+impl Trace {
+    pub fn to_json_lines(&self) -> String {
+        let mut string = String::new();
+        for (step, entry) in self.entries.iter().enumerate() {
+            string += &format!(
+                "{{\"step\":{},\"ip\":{},\"instruction\":\"{}\",\"acc_before\":{},\"acc_after\":{}}}\n",
+                step, entry.ip, entry.instruction, entry.acc_before, entry.acc_after
+            );
+        }
+        if let Some(end_ip) = self.end_ip {
+            let exit = match self.exit {
+                Some(ExitReason::Terminated) => ",\"exit\":\"terminated\"".to_owned(),
+                Some(ExitReason::Looped) => ",\"exit\":\"looped\"".to_owned(),
+                Some(ExitReason::JumpedOutOfBounds { target, .. }) => {
+                    format!(",\"exit\":\"jumped_out_of_bounds\",\"target\":{}", target)
+                }
+                None => String::new(),
+            };
+            string += &format!("{{\"end_ip\":{}{}}}\n", end_ip, exit);
+        }
+        string
+    }
+
+    /// Reads back the output of [`Self::to_json_lines`].
+    pub fn parse_json_lines(input: &str) -> Result<Self, TraceParseError> {
+        let mut trace = Trace::default();
+        for (idx, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let error = TraceParseError { line: idx + 1 };
+            if let Some(end_ip) = json_field(line, "end_ip") {
+                let end_ip = end_ip.parse().map_err(|_| error.clone())?;
+                trace.end_ip = Some(end_ip);
+                trace.exit = parse_exit(line, end_ip).ok_or(error)?;
+            } else {
+                trace.entries.push(parse_entry(line).ok_or(error)?);
+            }
+        }
+        Ok(trace)
+    }
+
+    /// Finds the first instruction that runs twice. The end ip counts as well if the program
+    /// looped, since [`crate::VirtualMachine::run_until_recursion`] stops right before repeating
+    /// an instruction.
+    pub fn find_cycle(&self) -> Option<Cycle> {
+        let mut first_seen = HashMap::new();
+        let end_ip = match self.exit {
+            Some(ExitReason::Looped) => self.end_ip,
+            _ => None,
+        };
+        let ips = self.entries.iter().map(|e| e.ip).chain(end_ip);
+        for (step, ip) in ips.enumerate() {
+            if let Some(&entry_step) = first_seen.get(&ip) {
+                return Some(Cycle {
+                    entry_step,
+                    entry_ip: ip,
+                    ips: self.entries[entry_step..step]
+                        .iter()
+                        .map(|e| e.ip)
+                        .collect(),
+                });
+            }
+            first_seen.insert(ip, step);
+        }
+        None
+    }
+
+    /// Lists the entries within `radius` steps of `step`, marking `step` itself.
+    pub fn replay(&self, step: usize, radius: usize) -> Option<String> {
+        if step >= self.entries.len() {
+            return None;
+        }
+
+        let start = step.saturating_sub(radius);
+        let end = (step + radius + 1).min(self.entries.len());
+        let mut string = String::new();
+        for (idx, entry) in self.entries.iter().enumerate().take(end).skip(start) {
+            string += &format!(
+                "{} {:>6}  ip {:>5}  {:<12} acc {} -> {}\n",
+                if idx == step { '>' } else { ' ' },
+                idx,
+                entry.ip,
+                entry.instruction.to_string(),
+                entry.acc_before,
+                entry.acc_after
+            );
+        }
+        Some(string)
+    }
+}
+
+/// Gets the raw value of a field in a flat JSON object, without any surrounding quotes.
+fn json_field<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let start = line.find(&format!("\"{}\":", key))? + key.len() + 3;
+    let rest = &line[start..];
+    match rest.strip_prefix('"') {
+        Some(rest) => rest.split('"').next(),
+        None => rest.split([',', '}']).next().map(str::trim),
+    }
+}
+
+/// Reads the optional exit reason of the last line. `None` means the line is invalid.
+fn parse_exit(line: &str, end_ip: usize) -> Option<Option<ExitReason>> {
+    let exit = match json_field(line, "exit") {
+        None => None,
+        Some("terminated") => Some(ExitReason::Terminated),
+        Some("looped") => Some(ExitReason::Looped),
+        Some("jumped_out_of_bounds") => Some(ExitReason::JumpedOutOfBounds {
+            ip: end_ip,
+            target: json_field(line, "target")?.parse().ok()?,
+        }),
+        Some(_) => return None,
+    };
+    Some(exit)
+}
+
+fn parse_entry(line: &str) -> Option<TraceEntry> {
+    Some(TraceEntry {
+        ip: json_field(line, "ip")?.parse().ok()?,
+        instruction: Instruction::parse(json_field(line, "instruction")?).ok()?,
+        acc_before: json_field(line, "acc_before")?.parse().ok()?,
+        acc_after: json_field(line, "acc_after")?.parse().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_input, tests::INPUT, ExitReason, VirtualMachine};
+
+    fn record() -> Trace {
+        let instructions = parse_input(INPUT).unwrap();
+        let mut vm = VirtualMachine::new(&instructions);
+        vm.enable_tracing();
+        assert_eq!(ExitReason::Looped, vm.run_until_recursion());
+        vm.take_trace().unwrap()
+    }
+
+    #[test]
+    fn test_cycle() {
+        let trace = record();
+        assert_eq!(7, trace.entries.len());
+        assert_eq!(
+            Some(Cycle {
+                entry_step: 1,
+                entry_ip: 1,
+                ips: vec![1, 2, 6, 7, 3, 4],
+            }),
+            trace.find_cycle()
+        );
+    }
+
+    #[test]
+    fn test_no_cycle_after_exit() {
+        for (program, exit) in &[
+            ("hlt", ExitReason::Terminated),
+            (
+                "nop +0\njmp +5",
+                ExitReason::JumpedOutOfBounds { ip: 1, target: 6 },
+            ),
+        ] {
+            let instructions = parse_input(program).unwrap();
+            let mut vm = VirtualMachine::new(&instructions);
+            vm.enable_tracing();
+            assert_eq!(*exit, vm.run_until_recursion());
+            let trace = vm.take_trace().unwrap();
+
+            assert_eq!(Some(*exit), trace.exit);
+            assert_eq!(None, trace.find_cycle());
+            let parsed = Trace::parse_json_lines(&trace.to_json_lines()).unwrap();
+            assert_eq!(trace, parsed);
+            assert_eq!(None, parsed.find_cycle());
+        }
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let trace = record();
+        let json = trace.to_json_lines();
+        assert_eq!(
+            Some(
+                "{\"step\":3,\"ip\":6,\"instruction\":\"acc +1\",\"acc_before\":1,\"acc_after\":2}"
+            ),
+            json.lines().nth(3)
+        );
+        assert_eq!(Ok(trace), Trace::parse_json_lines(&json));
+        assert_eq!(
+            Err(TraceParseError { line: 2 }),
+            Trace::parse_json_lines(
+                "{\"ip\":0,\"instruction\":\"nop +0\",\"acc_before\":0,\"acc_after\":0}\n{}"
+            )
+        );
+        assert_eq!(
+            Some("{\"end_ip\":1,\"exit\":\"looped\"}"),
+            json.lines().last()
+        );
+    }
+
+    #[test]
+    fn test_replay() {
+        let trace = record();
+        let replayed = trace.replay(4, 1).unwrap();
+        let lines = replayed.lines().collect::<Vec<_>>();
+        assert_eq!(3, lines.len());
+        assert_eq!(">      4  ip     7  jmp -4       acc 2 -> 2", lines[1]);
+        assert!(lines[0].starts_with("       3  ip     6"));
+
+        assert_eq!(None, trace.replay(7, 1));
+    }
+}
@@ -0,0 +1,274 @@
+use std::fmt;
+
+use crate::{
+    json::{self, Value},
+    parser::Field,
+    quote::quote,
+    schema::{Document, PASSPORT},
+    Passport,
+};
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Format {
+    /// One array with a passport per line.
+    Json,
+    /// One passport object per line.
+    JsonLines,
+    /// A header with every field, followed by a row per passport.
+    Csv,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "json" => Some(Format::Json),
+            "jsonl" => Some(Format::JsonLines),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// The fields of a passport as they count, in the order they first appear. Later values of a
+/// field replace earlier ones.
+fn effective_fields<'p>(passport: &'p Passport) -> Vec<(&'p str, &'p str)> {
+    let mut fields = Vec::<(&str, &str)>::new();
+    for field in &passport.fields {
+        match fields.iter_mut().find(|(key, _)| *key == field.key) {
+            Some(existing) => existing.1 = &field.value,
+            None => fields.push((&field.key, &field.value)),
+        }
+    }
+    fields
+}
+
+fn to_json_object(passport: &Passport) -> String {
+    let fields = effective_fields(passport)
+        .iter()
+        .map(|(key, value)| format!("{}:{}", quote(key), quote(value)))
+        .collect::<Vec<_>>();
+    let failures = passport
+        .validate()
+        .iter()
+        .map(|e| {
+            format!(
+                "{{\"field\":{},\"kind\":{},\"reason\":{}}}",
+                quote(e.field),
+                quote(&e.kind.to_string()),
+                quote(&e.reason)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    format!(
+        "{{\"line\":{},\"fields\":{{{}}},\"has_data\":{},\"is_valid\":{},\"failures\":[{}]}}",
+        passport.line,
+        fields.join(","),
+        passport.has_data(),
+        passport.is_valid(),
+        failures.join(",")
+    )
+}
+
+/// Quotes a CSV cell if it needs it.
+fn csv_cell(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_owned()
+    }
+}
+
+fn to_csv(passports: &[Passport]) -> String {
+    // the schema's fields first, then any others in the order they were seen
+    let mut keys = PASSPORT.fields.iter().map(|f| f.name).collect::<Vec<_>>();
+    for passport in passports {
+        for field in &passport.fields {
+            if !keys.contains(&&*field.key) {
+                keys.push(&field.key);
+            }
+        }
+    }
+
+    let header = ["line"]
+        .iter()
+        .chain(&keys)
+        .chain(&["has_data", "is_valid", "failures"])
+        .map(|cell| csv_cell(cell))
+        .collect::<Vec<_>>();
+    let mut string = header.join(",") + "\n";
+    for passport in passports {
+        let failures = passport
+            .validate()
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>();
+        let row = std::iter::once(passport.line.to_string())
+            .chain(
+                keys.iter()
+                    .map(|&key| csv_cell(passport.get(key).unwrap_or(""))),
+            )
+            .chain(vec![
+                passport.has_data().to_string(),
+                passport.is_valid().to_string(),
+                csv_cell(&failures.join("; ")),
+            ])
+            .collect::<Vec<_>>();
+        string += &row.join(",");
+        string += "\n";
+    }
+    string
+}
+
+/// Writes the fields of every passport along with whether it is complete and valid, and why not.
+pub fn export(passports: &[Passport], format: Format) -> String {
+    match format {
+        Format::Json if passports.is_empty() => "[]\n".to_owned(),
+        Format::Json => {
+            let objects = passports.iter().map(to_json_object).collect::<Vec<_>>();
+            format!("[\n{}\n]\n", objects.join(",\n"))
+        }
+        Format::JsonLines => passports.iter().map(|p| to_json_object(p) + "\n").collect(),
+        Format::Csv => to_csv(passports),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ImportError {
+    Json(json::JsonError),
+    /// The passport at this 1-based position isn't shaped like an exported one.
+    Passport(usize),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Json(error) => write!(f, "invalid JSON: {}", error),
+            ImportError::Passport(idx) => write!(
+                f,
+                "passport {}: expected string fields and a whole line number",
+                idx
+            ),
+        }
+    }
+}
+
+fn from_json_object(value: &Value) -> Option<Passport<'static>> {
+    let line = match value.get("line") {
+        Some(&Value::Number(line))
+            if line >= 0.0 && line.fract() == 0.0 && line <= usize::MAX as f64 =>
+        {
+            line as usize
+        }
+        None => 0,
+        _ => return None,
+    };
+    let entries = match value.get("fields")? {
+        Value::Object(entries) => entries,
+        _ => return None,
+    };
+
+    let mut fields = vec![];
+    for (key, value) in entries {
+        match value {
+            Value::String(value) => fields.push(Field {
+                key: key.clone().into(),
+                value: value.clone().into(),
+                line,
+                column: 1,
+            }),
+            _ => return None,
+        }
+    }
+    Some(Passport { fields, line })
+}
+
+/// Reads passports written as JSON or JSON Lines by [`export`]. Only the fields and line are read
+/// back, whether the passports are valid is worked out again. Every field points at the start of
+/// its passport.
+pub fn import(input: &str) -> Result<Vec<Passport<'static>>, ImportError> {
+    let values = json::parse_values(input).map_err(ImportError::Json)?;
+    let objects = values.iter().flat_map(|value| match value {
+        Value::Array(values) => values.iter().collect(),
+        value => vec![value],
+    });
+    objects
+        .enumerate()
+        .map(|(idx, value)| from_json_object(value).ok_or(ImportError::Passport(idx + 1)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_string;
+
+    const INPUT: &str = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd\n\
+        byr:1937 iyr:2017 cid:147 hgt:183cm\n\
+        \n\
+        hcl:\"a,b\" ecl:amb ecl:brn\n";
+
+    #[test]
+    fn test_export() {
+        let passports = parse_string(INPUT);
+
+        assert_eq!(
+            "{\"line\":4,\"fields\":{\"hcl\":\"\\\"a,b\\\"\",\"ecl\":\"brn\"},\
+            \"has_data\":false,\"is_valid\":false,\"failures\":[\
+            {\"field\":\"byr\",\"kind\":\"missing\",\"reason\":\"is missing\"},\
+            {\"field\":\"iyr\",\"kind\":\"missing\",\"reason\":\"is missing\"},\
+            {\"field\":\"eyr\",\"kind\":\"missing\",\"reason\":\"is missing\"},\
+            {\"field\":\"hgt\",\"kind\":\"missing\",\"reason\":\"is missing\"},\
+            {\"field\":\"hcl\",\"kind\":\"malformed\",\
+            \"reason\":\"\\\"a,b\\\" is not a # followed by six hex digits\"},\
+            {\"field\":\"pid\",\"kind\":\"missing\",\"reason\":\"is missing\"}]}",
+            export(&passports, Format::JsonLines)
+                .lines()
+                .nth(1)
+                .unwrap()
+        );
+
+        let csv = export(&passports, Format::Csv);
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(
+            "line,byr,iyr,eyr,hgt,hcl,ecl,pid,cid,has_data,is_valid,failures",
+            lines[0]
+        );
+        assert_eq!(
+            "1,1937,2017,2020,183cm,#fffffd,gry,860033327,147,true,true,",
+            lines[1]
+        );
+        assert!(lines[2].starts_with("4,,,,,\"\"\"a,b\"\"\",brn,,,false,false,\"byr is missing; "));
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let passports = parse_string(INPUT);
+        for format in [Format::Json, Format::JsonLines] {
+            let exported = export(&passports, format);
+            let imported = import(&exported).unwrap();
+            assert_eq!(2, imported.len());
+            assert_eq!(exported, export(&imported, format));
+        }
+        assert_eq!("[]\n", export(&[], Format::Json));
+        assert_eq!(Ok(0), import("[]").map(|p| p.len()));
+
+        assert_eq!(
+            Err(ImportError::Passport(2)),
+            import("{\"fields\":{}}\n{\"fields\":{\"byr\":1920}}")
+        );
+        assert!(matches!(import("[{]"), Err(ImportError::Json(_))));
+        assert_eq!(
+            Ok(vec![3]),
+            import("{\"line\":3,\"fields\":{}}").map(|p| p.iter().map(|p| p.line).collect())
+        );
+        for line in ["1.5", "-1", "\"3\"", "1e400"] {
+            assert_eq!(
+                Err(ImportError::Passport(1)),
+                import(&format!("{{\"line\":{},\"fields\":{{}}}}", line)),
+                "{}",
+                line
+            );
+        }
+    }
+}
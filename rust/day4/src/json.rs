@@ -0,0 +1,274 @@
+use std::{fmt, iter::Peekable, str::CharIndices};
+
+/// Just enough JSON to read back exported passports.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// Keys in the order they were given.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// The last value of `key`, if this is an object that has it.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(entries) => entries.iter().rev().find(|(k, _)| k == key).map(|e| &e.1),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct JsonError {
+    /// 1-based.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Whether `s` follows the JSON number grammar, which is stricter than [`f64`]'s `FromStr`: no
+/// `inf`, `NaN`, leading `+` or `.`, or leading zeros.
+fn is_number(s: &str) -> bool {
+    fn digits(bytes: &[u8]) -> usize {
+        bytes.iter().take_while(|b| b.is_ascii_digit()).count()
+    }
+
+    let bytes = s.as_bytes();
+    let mut i = (bytes.first() == Some(&b'-')) as usize;
+    match digits(&bytes[i..]) {
+        0 => return false,
+        n if n > 1 && bytes[i] == b'0' => return false,
+        n => i += n,
+    }
+    if bytes.get(i) == Some(&b'.') {
+        match digits(&bytes[i + 1..]) {
+            0 => return false,
+            n => i += 1 + n,
+        }
+    }
+    if let Some(b'e' | b'E') = bytes.get(i) {
+        i += 1;
+        if let Some(b'+' | b'-') = bytes.get(i) {
+            i += 1;
+        }
+        match digits(&bytes[i..]) {
+            0 => return false,
+            n => i += n,
+        }
+    }
+    i == bytes.len()
+}
+
+struct Reader<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&mut self, message: &str) -> JsonError {
+        let offset = self.chars.peek().map_or(self.input.len(), |&(i, _)| i);
+        JsonError {
+            line: self.input[..offset].matches('\n').count() + 1,
+            message: message.to_owned(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), JsonError> {
+        self.skip_whitespace();
+        match self.chars.next_if(|&(_, c)| c == expected) {
+            Some(_) => Ok(()),
+            None => Err(self.error(&format!("expected '{}'", expected))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, JsonError> {
+        self.skip_whitespace();
+        let start = match self.chars.peek() {
+            Some(&(start, _)) => start,
+            None => return Err(self.error("unexpected end of input")),
+        };
+        match self.input[start..].chars().next().unwrap() {
+            '"' => Ok(Value::String(self.string()?)),
+            '[' => {
+                self.chars.next();
+                let mut values = vec![];
+                self.skip_whitespace();
+                if self.chars.next_if(|&(_, c)| c == ']').is_none() {
+                    loop {
+                        values.push(self.value()?);
+                        self.skip_whitespace();
+                        match self.chars.next() {
+                            Some((_, ',')) => continue,
+                            Some((_, ']')) => break,
+                            _ => return Err(self.error("expected ',' or ']'")),
+                        }
+                    }
+                }
+                Ok(Value::Array(values))
+            }
+            '{' => {
+                self.chars.next();
+                let mut entries = vec![];
+                self.skip_whitespace();
+                if self.chars.next_if(|&(_, c)| c == '}').is_none() {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        self.expect(':')?;
+                        entries.push((key, self.value()?));
+                        self.skip_whitespace();
+                        match self.chars.next() {
+                            Some((_, ',')) => continue,
+                            Some((_, '}')) => break,
+                            _ => return Err(self.error("expected ',' or '}'")),
+                        }
+                    }
+                }
+                Ok(Value::Object(entries))
+            }
+            _ => {
+                let mut end = start;
+                while let Some((i, c)) = self
+                    .chars
+                    .next_if(|&(_, c)| c.is_ascii_alphanumeric() || "+-.".contains(c))
+                {
+                    end = i + c.len_utf8();
+                }
+                match &self.input[start..end] {
+                    "null" => Ok(Value::Null),
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    number => match number.parse() {
+                        Ok(parsed) if is_number(number) => Ok(Value::Number(parsed)),
+                        _ => Err(self.error("expected a value")),
+                    },
+                }
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        if self.chars.next_if(|&(_, c)| c == '"').is_none() {
+            return Err(self.error("expected a string"));
+        }
+
+        let mut string = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(string),
+                Some((_, '\\')) => {
+                    let escaped = match self.chars.next() {
+                        Some((_, 'n')) => '\n',
+                        Some((_, 't')) => '\t',
+                        Some((_, 'r')) => '\r',
+                        Some((_, 'b')) => '\u{8}',
+                        Some((_, 'f')) => '\u{c}',
+                        Some((_, 'u')) => {
+                            let hex = (0..4)
+                                .filter_map(|_| self.chars.next().map(|(_, c)| c))
+                                .collect::<String>();
+                            match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                                Some(c) => c,
+                                None => return Err(self.error("invalid \\u escape")),
+                            }
+                        }
+                        Some((_, c @ ('"' | '\\' | '/'))) => c,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    string.push(escaped);
+                }
+                Some((_, c)) => string.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+}
+
+/// Reads every value in `input`, so both a single document and JSON Lines work.
+pub fn parse_values(input: &str) -> Result<Vec<Value>, JsonError> {
+    let mut reader = Reader {
+        input,
+        chars: input.char_indices().peekable(),
+    };
+    let mut values = vec![];
+    loop {
+        reader.skip_whitespace();
+        if reader.chars.peek().is_none() {
+            return Ok(values);
+        }
+        values.push(reader.value()?);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let values =
+            parse_values("{\"a\": [1, -2.5e1, true, null], \"b\": \"x\\\"\\u00e9\\n\"}\n{}\n[]")
+                .unwrap();
+        assert_eq!(
+            vec![
+                Value::Object(vec![
+                    (
+                        "a".to_owned(),
+                        Value::Array(vec![
+                            Value::Number(1.0),
+                            Value::Number(-25.0),
+                            Value::Bool(true),
+                            Value::Null,
+                        ])
+                    ),
+                    ("b".to_owned(), Value::String("x\"é\n".to_owned())),
+                ]),
+                Value::Object(vec![]),
+                Value::Array(vec![]),
+            ],
+            values
+        );
+        assert_eq!(
+            Some(&Value::String("x\"é\n".to_owned())),
+            values[0].get("b")
+        );
+
+        let error = parse_values("[1,\n2,\n}").unwrap_err();
+        assert_eq!("line 3: expected a value", error.to_string());
+        assert!(parse_values("\"abc").is_err());
+        assert!(parse_values("{\"a\" 1}").is_err());
+
+        assert_eq!(
+            Ok(vec![
+                Value::Number(0.0),
+                Value::Number(-0.5),
+                Value::Number(1e-3),
+                Value::Number(2e10),
+            ]),
+            parse_values("0 -0.5 1E-3 2e+10")
+        );
+        for number in [
+            "inf", "-inf", "NaN", "+1", "01", "1.", ".5", "1e", "--1", "0x10",
+        ] {
+            assert_eq!(
+                "line 1: expected a value",
+                parse_values(number).unwrap_err().to_string(),
+                "{}",
+                number
+            );
+        }
+    }
+}
@@ -1,118 +1,146 @@
 std::include!("../../helpers.rs");
 
-#[derive(Debug, Default, Clone)]
+mod export;
+mod json;
+mod parser;
+#[path = "../../quote.rs"]
+mod quote;
+mod report;
+mod schema;
+
+use export::Format;
+use parser::{Field, ParseOptions, Policy};
+use schema::{Document, FieldError, PASSPORT};
+
+/// The fields of a passport in the order they were given.
+#[derive(Debug, PartialEq, Eq, Default, Clone)]
 struct Passport<'a> {
-    pub byr: Option<&'a str>,
-    pub iyr: Option<&'a str>,
-    pub eyr: Option<&'a str>,
-    pub hgt: Option<&'a str>,
-    pub hcl: Option<&'a str>,
-    pub ecl: Option<&'a str>,
-    pub pid: Option<&'a str>,
+    pub fields: Vec<Field<'a>>,
+    /// The line the passport starts on, 1-based.
+    pub line: usize,
 }
 
 impl<'a> Passport<'a> {
-    pub fn parse_line(mut self, line: &'a str) -> Self {
-        for data in line.split(' ') {
-            match &data[0..3] {
-                "byr" => self.byr = Some(&data[4..]),
-                "iyr" => self.iyr = Some(&data[4..]),
-                "eyr" => self.eyr = Some(&data[4..]),
-                "hgt" => self.hgt = Some(&data[4..]),
-                "hcl" => self.hcl = Some(&data[4..]),
-                "ecl" => self.ecl = Some(&data[4..]),
-                "pid" => self.pid = Some(&data[4..]),
-                "cid" => (),
-                unk => panic!("Unknown data type: {}", unk),
-            }
-        }
-
-        self
+    /// The last occurrence of `key`, which is the one that counts.
+    pub fn field(&self, key: &str) -> Option<&Field<'a>> {
+        self.fields.iter().rev().find(|f| f.key == key)
     }
 
     pub fn has_data(&self) -> bool {
-        self.byr.is_some()
-            && self.iyr.is_some()
-            && self.eyr.is_some()
-            && self.hgt.is_some()
-            && self.hcl.is_some()
-            && self.ecl.is_some()
-            && self.pid.is_some()
+        PASSPORT.has_required(self)
     }
 
     pub fn is_valid(&self) -> bool {
-        if !self.has_data() {
-            return false;
-        }
-
-        let byr = self.byr.unwrap().parse::<usize>().unwrap();
-        if byr < 1920 || byr > 2002 {
-            return false;
-        }
+        PASSPORT.is_valid(self)
+    }
 
-        let iyr = self.iyr.unwrap().parse::<usize>().unwrap();
-        if iyr < 2010 || iyr > 2020 {
-            return false;
-        }
+    pub fn validate(&self) -> Vec<FieldError> {
+        PASSPORT.validate(self)
+    }
+}
 
-        let eyr = self.eyr.unwrap().parse::<usize>().unwrap();
-        if eyr < 2020 || eyr > 2030 {
-            return false;
-        }
+impl Document for Passport<'_> {
+    /// Later values of a field replace earlier ones.
+    fn get(&self, field: &str) -> Option<&str> {
+        self.field(field).map(|f| &*f.value)
+    }
+}
 
-        let hgt = self.hgt.unwrap();
-        if hgt.len() <= 3 {
-            return false;
-        }
+/// Parses with the default options, which only warn, so this can't fail.
+fn parse_string(data: &str) -> Vec<Passport<'_>> {
+    parser::parse(data, &ParseOptions::default())
+        .expect("warnings are not errors")
+        .passports
+}
 
-        let hgt = (
-            &hgt[..hgt.len() - 2].parse::<usize>().unwrap(),
-            &hgt[hgt.len() - 2..],
-        );
-        if !match hgt.1 {
-            "cm" => *hgt.0 >= 150 && *hgt.0 <= 193,
-            "in" => *hgt.0 >= 59 && *hgt.0 <= 76,
-            _ => false,
-        } {
-            return false;
+const USAGE: &str =
+    "usage: day4 [report [--all] [--unknown|--duplicate|--malformed ignore|warn|error]...]";
+
+/// Parses the flags of `report` into whether to list every passport and the parse options.
+fn parse_report_args(args: &[&str]) -> Option<(bool, ParseOptions)> {
+    let mut all = false;
+    let mut options = ParseOptions::default();
+    let mut iter = args.iter();
+    while let Some(&flag) = iter.next() {
+        let policy = match flag {
+            "--all" => {
+                all = true;
+                continue;
+            }
+            _ => Policy::parse(iter.next()?)?,
+        };
+        match flag {
+            "--unknown" => options.unknown_keys = policy,
+            "--duplicate" => options.duplicate_keys = policy,
+            "--malformed" => options.malformed_tokens = policy,
+            _ => return None,
         }
+    }
+    Some((all, options))
+}
 
-        let hcl = self.hcl.unwrap();
-        if hcl.len() != 7 || hcl.bytes().nth(0).unwrap() != '#' as u8 || !hcl[1..].bytes().all(|b| b.is_ascii_hexdigit()) {
-            return false;
+fn run_report(all: bool, options: &ParseOptions) {
+    let data = read_stdin();
+    let parsed = match parser::parse(&data, options) {
+        Ok(parsed) => parsed,
+        Err(diagnostics) => {
+            for diagnostic in diagnostics {
+                eprintln!("{}", diagnostic);
+            }
+            std::process::exit(1);
         }
+    };
 
-        let ecl = self.ecl.unwrap();
+    for warning in &parsed.warnings {
+        eprintln!("{}", warning);
+    }
+    if all {
+        print!("{}", report::details(&parsed.passports));
+    }
+    print!("{}", report::summary(&parsed.passports));
+}
 
-        if !matches!(ecl, "amb" | "blu" | "brn" | "gry" | "grn" | "hzl" | "oth") {
-            return false;
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    match args.as_slice() {
+        [] => run_challenge(),
+        ["report", flags @ ..] => match parse_report_args(flags) {
+            Some((all, options)) => run_report(all, &options),
+            None => {
+                eprintln!("{}", USAGE);
+                std::process::exit(1);
+            }
+        },
+        ["export", format] if Format::parse(format).is_some() => {
+            let format = Format::parse(format).unwrap();
+            print!("{}", export::export(&parse_string(&read_stdin()), format));
         }
-
-        let pid = self.pid.unwrap();
-        if pid.len() != 9 || !pid.bytes().all(|c| c.is_ascii_digit()) {
-            return false;
+        ["import"] => run_import(Format::Json),
+        ["import", format] if Format::parse(format).is_some() => {
+            run_import(Format::parse(format).unwrap())
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
         }
-
-        return true;
     }
 }
 
-fn parse_string(data: &str) -> Vec<Passport> {
-    let mut vec = Vec::new();
-    for lines in data.split("\n\n") {
-        let mut flags: Passport = Passport::default();
-        for line in lines.split('\n').filter(|l| l.len() > 0) {
-            flags = flags.parse_line(line);
+/// Reads exported JSON and writes it out again in `format`, with the outcomes worked out anew.
+fn run_import(format: Format) {
+    match export::import(&read_stdin()) {
+        Ok(passports) => print!("{}", export::export(&passports, format)),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
         }
-        vec.push(flags);
     }
-
-    vec
 }
 
-fn main() {
+fn run_challenge() {
     let (_, time_total) = time(|| {
-        let (str, time_reading) = time(|| read_stdin());
+        let (str, time_reading) = time(read_stdin);
         let (data, time_parsing) = time(|| parse_string(&str));
 
         let (count_correct_1, time_solving_1) =
@@ -134,6 +162,7 @@ fn main() {
 }
 
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
 mod test {
     use crate::parse_string;
 
@@ -178,6 +207,7 @@ mod test {
         let data = parse_string(str);
 
         assert_eq!(194, data.iter().filter(|x| x.is_valid()).count());
+        assert_eq!(194, data.iter().filter(|x| x.validate().is_empty()).count());
         assert!(true);
     }
 }
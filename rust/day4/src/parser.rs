@@ -0,0 +1,254 @@
+use std::{borrow::Cow, fmt};
+
+use crate::{
+    schema::{Schema, PASSPORT},
+    Passport,
+};
+
+/// A `key:value` pair, with where it starts in the input. Parsed fields borrow from the input,
+/// imported ones own their text.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Field<'a> {
+    pub key: Cow<'a, str>,
+    pub value: Cow<'a, str>,
+    /// 1-based.
+    pub line: usize,
+    /// 1-based, counting characters.
+    pub column: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Policy {
+    Ignore,
+    Warn,
+    Error,
+}
+
+impl Policy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "ignore" => Some(Policy::Ignore),
+            "warn" => Some(Policy::Warn),
+            "error" => Some(Policy::Error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct ParseOptions {
+    /// Decides which keys are unknown.
+    pub schema: Schema,
+    /// Keys that are not in the schema. They are kept unless this is [`Policy::Error`].
+    pub unknown_keys: Policy,
+    /// Keys that appear more than once in a passport. The last value is used.
+    pub duplicate_keys: Policy,
+    /// Tokens without a `:`. They are always left out.
+    pub malformed_tokens: Policy,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            schema: PASSPORT,
+            unknown_keys: Policy::Warn,
+            duplicate_keys: Policy::Warn,
+            malformed_tokens: Policy::Warn,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(
+            f,
+            "{}: line {}, column {}: {}",
+            severity, self.line, self.column, self.message
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Parsed<'a> {
+    pub passports: Vec<Passport<'a>>,
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// Splits `line` on any whitespace, giving every token with its 1-based column.
+fn tokens(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut column = 0;
+    let mut start = None;
+    let mut chars = line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ' ')));
+    std::iter::from_fn(move || {
+        for (idx, c) in &mut chars {
+            column += 1;
+            match (c.is_whitespace(), start) {
+                (false, None) => start = Some((idx, column)),
+                (true, Some((token_start, token_column))) => {
+                    start = None;
+                    return Some((token_column, &line[token_start..idx]));
+                }
+                _ => (),
+            }
+        }
+        None
+    })
+}
+
+/// Reads passports separated by blank lines, with `key:value` fields separated by any whitespace.
+/// Returns every diagnostic if any of them is an error.
+pub fn parse<'a>(input: &'a str, options: &ParseOptions) -> Result<Parsed<'a>, Vec<Diagnostic>> {
+    let mut parsed = Parsed::default();
+    let mut diagnostics = vec![];
+    let mut report = |policy, line, column, message| {
+        let severity = match policy {
+            Policy::Ignore => return,
+            Policy::Warn => Severity::Warning,
+            Policy::Error => Severity::Error,
+        };
+        diagnostics.push(Diagnostic {
+            severity,
+            line,
+            column,
+            message,
+        });
+    };
+
+    let mut current: Option<Passport> = None;
+    for (idx, text) in input.lines().enumerate() {
+        let line = idx + 1;
+        if text.trim().is_empty() {
+            parsed.passports.extend(current.take());
+            continue;
+        }
+
+        let passport = current.get_or_insert_with(|| Passport {
+            fields: vec![],
+            line,
+        });
+        for (column, token) in tokens(text) {
+            let (key, value) = match token.find(':') {
+                Some(colon) => (&token[..colon], &token[colon + 1..]),
+                None => {
+                    let message = format!("'{}' is not a key:value pair", token);
+                    report(options.malformed_tokens, line, column, message);
+                    continue;
+                }
+            };
+
+            if passport.fields.iter().any(|f| f.key == key) {
+                let message = format!("duplicate field '{}'", key);
+                report(options.duplicate_keys, line, column, message);
+            }
+            if options.schema.field(key).is_none() {
+                let message = format!("unknown field '{}'", key);
+                report(options.unknown_keys, line, column, message);
+            }
+
+            passport.fields.push(Field {
+                key: key.into(),
+                value: value.into(),
+                line,
+                column,
+            });
+        }
+    }
+    parsed.passports.extend(current);
+
+    if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+        return Err(diagnostics);
+    }
+    parsed.warnings = diagnostics;
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::Document;
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(
+            vec![(1, "a:1"), (6, "bé:2"), (12, "c")],
+            tokens("a:1 \tbé:2  c\t").collect::<Vec<_>>()
+        );
+        assert_eq!(0, tokens("   ").count());
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let input = "ecl:gry\tpid:860033327   eyr:2020\r\nhcl:#fffffd\r\n \t\r\n\r\n\
+            iyr:2013 ecl:amb\r\n";
+        let parsed = parse(input, &ParseOptions::default()).unwrap();
+
+        assert_eq!(2, parsed.passports.len());
+        assert!(parsed.warnings.is_empty());
+        assert_eq!(Some("#fffffd"), parsed.passports[0].get("hcl"));
+        assert_eq!(1, parsed.passports[0].line);
+        assert_eq!(5, parsed.passports[1].line);
+        assert_eq!(
+            Field {
+                key: "eyr".into(),
+                value: "2020".into(),
+                line: 1,
+                column: 25,
+            },
+            parsed.passports[0].fields[2]
+        );
+    }
+
+    #[test]
+    fn test_policies() {
+        let input = "byr:1920 x\nfoo:bar byr:1930";
+
+        let parsed = parse(input, &ParseOptions::default()).unwrap();
+        let messages = parsed
+            .warnings
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "warning: line 1, column 10: 'x' is not a key:value pair",
+                "warning: line 2, column 1: unknown field 'foo'",
+                "warning: line 2, column 9: duplicate field 'byr'",
+            ],
+            messages
+        );
+        let passport = &parsed.passports[0];
+        assert_eq!(Some("1930"), passport.get("byr"));
+        assert_eq!(Some("bar"), passport.get("foo"));
+
+        let options = ParseOptions {
+            unknown_keys: Policy::Ignore,
+            duplicate_keys: Policy::Error,
+            malformed_tokens: Policy::Ignore,
+            ..ParseOptions::default()
+        };
+        let errors = parse(input, &options).unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!(Severity::Error, errors[0].severity);
+        assert_eq!((2, 9), (errors[0].line, errors[0].column));
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+
+use crate::{schema::FailureKind, Passport};
+
+/// Counts the failures of a batch of passports per field, and per kind of failure within each
+/// field.
+pub fn summary(passports: &[Passport]) -> String {
+    let mut fields = BTreeMap::<&str, BTreeMap<FailureKind, usize>>::new();
+    let mut valid = 0;
+    for passport in passports {
+        let errors = passport.validate();
+        if errors.is_empty() {
+            valid += 1;
+        }
+        for error in errors {
+            *fields
+                .entry(error.field)
+                .or_default()
+                .entry(error.kind)
+                .or_default() += 1;
+        }
+    }
+
+    let mut string = format!("{} of {} passports are valid\n", valid, passports.len());
+    for (field, kinds) in fields {
+        let total = kinds.values().sum::<usize>();
+        let kinds = kinds
+            .iter()
+            .map(|(kind, count)| format!("{} {}", count, kind))
+            .collect::<Vec<_>>();
+        let plural = if total == 1 { "" } else { "s" };
+        string += &format!(
+            "{}: {} failure{} ({})\n",
+            field,
+            total,
+            plural,
+            kinds.join(", ")
+        );
+    }
+    string
+}
+
+/// Lists every failure of every invalid passport, numbered from 1, with where the field is in the
+/// input. Missing fields point at the start of the passport.
+pub fn details(passports: &[Passport]) -> String {
+    let mut string = String::new();
+    for (idx, passport) in passports.iter().enumerate() {
+        for error in passport.validate() {
+            let (line, column) = passport
+                .field(error.field)
+                .map_or((passport.line, 1), |f| (f.line, f.column));
+            string += &format!(
+                "passport {}, line {}, column {}: {}\n",
+                idx + 1,
+                line,
+                column,
+                error
+            );
+        }
+    }
+    string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_string;
+
+    #[test]
+    fn test_summary() {
+        let data = parse_string(include_str!("../test_input2_invalid.txt"));
+        assert_eq!(
+            "0 of 4 passports are valid\n\
+            byr: 1 failure (1 out of range)\n\
+            ecl: 1 failure (1 not allowed)\n\
+            eyr: 3 failures (3 out of range)\n\
+            hcl: 2 failures (2 malformed)\n\
+            hgt: 2 failures (1 malformed, 1 out of range)\n\
+            iyr: 1 failure (1 out of range)\n\
+            pid: 2 failures (1 malformed, 1 wrong length)\n",
+            summary(&data)
+        );
+    }
+
+    #[test]
+    fn test_details() {
+        let data = parse_string("hcl:#123abc ecl:brn\npid:01234567 hgt:180cm\n\n\nbyr:1920");
+        let details = details(&data);
+        assert!(details.starts_with(
+            "passport 1, line 1, column 1: byr is missing\n\
+            passport 1, line 1, column 1: iyr is missing\n\
+            passport 1, line 1, column 1: eyr is missing\n\
+            passport 1, line 2, column 1: pid has 8 digits instead of 9\n"
+        ));
+        assert!(details.contains("passport 2, line 5, column 1: iyr is missing\n"));
+    }
+}
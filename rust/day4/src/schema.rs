@@ -0,0 +1,373 @@
+use std::fmt;
+
+/// Anything with named text fields that can be checked against a [`Schema`].
+pub trait Document {
+    fn get(&self, field: &str) -> Option<&str>;
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Validator {
+    /// Any value is accepted.
+    Any,
+    /// A four digit year, inclusive.
+    Year {
+        min: u32,
+        max: u32,
+    },
+    /// A number followed by one of the units, each with their own inclusive range.
+    Measurement(&'static [Unit]),
+    /// `#` followed by six hexadecimal digits.
+    HexColor,
+    OneOf(&'static [&'static str]),
+    /// Exactly this many digits, leading zeroes included.
+    Digits(usize),
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Unit {
+    pub name: &'static str,
+    pub min: u32,
+    pub max: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct FieldSpec {
+    pub name: &'static str,
+    pub required: bool,
+    pub validator: Validator,
+}
+
+/// Describes which fields a document needs and what their values should look like. Fields that
+/// aren't in the schema are ignored.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Schema {
+    pub fields: &'static [FieldSpec],
+}
+
+pub const PASSPORT: Schema = Schema {
+    fields: &[
+        FieldSpec {
+            name: "byr",
+            required: true,
+            validator: Validator::Year {
+                min: 1920,
+                max: 2002,
+            },
+        },
+        FieldSpec {
+            name: "iyr",
+            required: true,
+            validator: Validator::Year {
+                min: 2010,
+                max: 2020,
+            },
+        },
+        FieldSpec {
+            name: "eyr",
+            required: true,
+            validator: Validator::Year {
+                min: 2020,
+                max: 2030,
+            },
+        },
+        FieldSpec {
+            name: "hgt",
+            required: true,
+            validator: Validator::Measurement(&[
+                Unit {
+                    name: "cm",
+                    min: 150,
+                    max: 193,
+                },
+                Unit {
+                    name: "in",
+                    min: 59,
+                    max: 76,
+                },
+            ]),
+        },
+        FieldSpec {
+            name: "hcl",
+            required: true,
+            validator: Validator::HexColor,
+        },
+        FieldSpec {
+            name: "ecl",
+            required: true,
+            validator: Validator::OneOf(&["amb", "blu", "brn", "gry", "grn", "hzl", "oth"]),
+        },
+        FieldSpec {
+            name: "pid",
+            required: true,
+            validator: Validator::Digits(9),
+        },
+        FieldSpec {
+            name: "cid",
+            required: false,
+            validator: Validator::Any,
+        },
+    ],
+};
+
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
+pub enum FailureKind {
+    Missing,
+    /// The value doesn't have the right shape, such as letters where digits should be.
+    Malformed,
+    OutOfRange,
+    NotAllowed,
+    WrongLength,
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FailureKind::Missing => "missing",
+            FailureKind::Malformed => "malformed",
+            FailureKind::OutOfRange => "out of range",
+            FailureKind::NotAllowed => "not allowed",
+            FailureKind::WrongLength => "wrong length",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FieldError {
+    pub field: &'static str,
+    /// `None` if the field is missing.
+    pub value: Option<String>,
+    pub kind: FailureKind,
+    /// Describes the problem, to be shown after the field name.
+    pub reason: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.field, self.reason)
+    }
+}
+
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+impl Validator {
+    pub fn check(&self, value: &str) -> bool {
+        self.validate(value).is_ok()
+    }
+
+    /// Explains what is wrong with `value`, if anything.
+    pub fn validate(&self, value: &str) -> Result<(), (FailureKind, String)> {
+        let fail = |kind, reason: String| Err((kind, reason));
+        match *self {
+            Validator::Any => Ok(()),
+            Validator::Year { .. } if value.len() != 4 || !is_digits(value) => fail(
+                FailureKind::Malformed,
+                format!("{} is not a four digit year", value),
+            ),
+            Validator::Year { min, max } => match value.parse::<u32>().unwrap() {
+                year if year < min => fail(
+                    FailureKind::OutOfRange,
+                    format!("{} is before {}", value, min),
+                ),
+                year if year > max => fail(
+                    FailureKind::OutOfRange,
+                    format!("{} is after {}", value, max),
+                ),
+                _ => Ok(()),
+            },
+            Validator::Measurement(units) => {
+                let unit = units.iter().find(|u| value.ends_with(u.name));
+                let number = unit.map(|u| &value[..value.len() - u.name.len()]);
+                match (unit, number) {
+                    (Some(unit), Some(number)) if is_digits(number) => {
+                        match number.parse::<u32>() {
+                            Ok(n) if n < unit.min => fail(
+                                FailureKind::OutOfRange,
+                                format!("{} is below {}{}", value, unit.min, unit.name),
+                            ),
+                            Ok(n) if n <= unit.max => Ok(()),
+                            _ => fail(
+                                FailureKind::OutOfRange,
+                                format!("{} exceeds {}{}", value, unit.max, unit.name),
+                            ),
+                        }
+                    }
+                    (Some(_), _) => fail(
+                        FailureKind::Malformed,
+                        format!("{} does not start with a number", value),
+                    ),
+                    (None, _) => {
+                        let names = units.iter().map(|u| u.name).collect::<Vec<_>>();
+                        fail(
+                            FailureKind::Malformed,
+                            format!("{} does not end in {}", value, names.join(" or ")),
+                        )
+                    }
+                }
+            }
+            Validator::HexColor => match value.strip_prefix('#') {
+                Some(hex) if hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()) => Ok(()),
+                _ => fail(
+                    FailureKind::Malformed,
+                    format!("{} is not a # followed by six hex digits", value),
+                ),
+            },
+            Validator::OneOf(options) if options.contains(&value) => Ok(()),
+            Validator::OneOf(options) => fail(
+                FailureKind::NotAllowed,
+                format!("{} is not one of {}", value, options.join(", ")),
+            ),
+            Validator::Digits(_) if !is_digits(value) => {
+                fail(FailureKind::Malformed, format!("{} is not a number", value))
+            }
+            Validator::Digits(count) if value.len() != count => fail(
+                FailureKind::WrongLength,
+                format!("has {} digits instead of {}", value.len(), count),
+            ),
+            Validator::Digits(_) => Ok(()),
+        }
+    }
+}
+
+impl Schema {
+    pub fn field(&self, name: &str) -> Option<&FieldSpec> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /// Whether every required field is present, regardless of its value.
+    pub fn has_required(&self, document: &impl Document) -> bool {
+        self.fields
+            .iter()
+            .filter(|f| f.required)
+            .all(|f| document.get(f.name).is_some())
+    }
+
+    /// Whether every required field is present and every known field has a valid value.
+    pub fn is_valid(&self, document: &impl Document) -> bool {
+        self.fields.iter().all(|f| match document.get(f.name) {
+            Some(value) => f.validator.check(value),
+            None => !f.required,
+        })
+    }
+
+    /// Every problem with the document, in the order of the fields in the schema.
+    pub fn validate(&self, document: &impl Document) -> Vec<FieldError> {
+        let mut errors = vec![];
+        for spec in self.fields {
+            let value = document.get(spec.name);
+            let result = match value {
+                Some(value) => spec.validator.validate(value),
+                None if spec.required => Err((FailureKind::Missing, "is missing".to_owned())),
+                None => Ok(()),
+            };
+
+            if let Err((kind, reason)) = result {
+                errors.push(FieldError {
+                    field: spec.name,
+                    value: value.map(str::to_owned),
+                    kind,
+                    reason,
+                });
+            }
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validators() {
+        let year = Validator::Year {
+            min: 1920,
+            max: 2002,
+        };
+        assert!(year.check("2002"));
+        assert!(!year.check("2003"));
+        assert!(!year.check("02002"));
+        assert!(!year.check("abcd"));
+
+        let height = PASSPORT.field("hgt").unwrap().validator;
+        assert!(height.check("60in"));
+        assert!(height.check("190cm"));
+        assert!(!height.check("190in"));
+        assert!(!height.check("190"));
+        assert!(!height.check("cm"));
+
+        assert!(Validator::HexColor.check("#123abc"));
+        assert!(!Validator::HexColor.check("#123abz"));
+        assert!(!Validator::HexColor.check("123abc"));
+
+        assert!(Validator::Digits(9).check("000000001"));
+        assert!(!Validator::Digits(9).check("0123456789"));
+    }
+
+    struct Pairs(Vec<(&'static str, &'static str)>);
+
+    impl Document for Pairs {
+        fn get(&self, field: &str) -> Option<&str> {
+            self.0.iter().find(|(k, _)| *k == field).map(|(_, v)| *v)
+        }
+    }
+
+    #[test]
+    fn test_custom_schema() {
+        const BADGE: Schema = Schema {
+            fields: &[
+                FieldSpec {
+                    name: "id",
+                    required: true,
+                    validator: Validator::Digits(4),
+                },
+                FieldSpec {
+                    name: "lvl",
+                    required: false,
+                    validator: Validator::OneOf(&["guest", "staff"]),
+                },
+            ],
+        };
+
+        assert!(BADGE.is_valid(&Pairs(vec![("id", "0042")])));
+        assert!(BADGE.is_valid(&Pairs(vec![("id", "0042"), ("lvl", "staff")])));
+        assert!(!BADGE.is_valid(&Pairs(vec![("id", "0042"), ("lvl", "admin")])));
+        assert!(!BADGE.has_required(&Pairs(vec![("lvl", "staff")])));
+    }
+
+    #[test]
+    fn test_reasons() {
+        let document = Pairs(vec![
+            ("byr", "1919"),
+            ("iyr", "20x0"),
+            ("hgt", "190in"),
+            ("hcl", "123abc"),
+            ("ecl", "red"),
+            ("pid", "0123456789"),
+        ]);
+        let errors = PASSPORT.validate(&document);
+        let messages = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                "byr 1919 is before 1920",
+                "iyr 20x0 is not a four digit year",
+                "eyr is missing",
+                "hgt 190in exceeds 76in",
+                "hcl 123abc is not a # followed by six hex digits",
+                "ecl red is not one of amb, blu, brn, gry, grn, hzl, oth",
+                "pid has 10 digits instead of 9",
+            ],
+            messages
+        );
+        assert_eq!(FailureKind::Missing, errors[2].kind);
+        assert_eq!(None, errors[2].value);
+        assert_eq!(Some("0123456789".to_owned()), errors[6].value);
+
+        assert!(PASSPORT.validate(&Pairs(vec![("hgt", "cm")]))[3]
+            .reason
+            .contains("does not start with a number"));
+    }
+}
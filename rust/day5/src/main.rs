@@ -1,28 +1,13 @@
-use std::convert::TryInto;
-
 std::include!("../../helpers.rs");
 
-// NOTE: could be made cleaner without overloads RFC2000 gets implementerd
-// https://rust-lang.github.io/rfcs/2000-const-generics.html
-fn decode_partition_10(data: &[u8; 10]) -> usize {
-    data.iter()
-        .enumerate()
-        .map(|(i, c)| match c {
-            b'B' | b'R' => 1 << (10 - 1 - i),
-            b'F' | b'L' => 0,
-            _ => panic!("Unknown char {}", c),
-        })
-        .sum()
-}
+mod pass;
+mod report;
+
+use pass::{Alphabet, Layout, STANDARD};
+use report::SeatReport;
 
 fn solve_part_1(data: &[bool; KEYSPACE]) -> usize {
-    data.iter()
-        .enumerate()
-        .rev()
-        .filter(|(_, b)| **b)
-        .next()
-        .unwrap()
-        .0
+    data.iter().rposition(|b| *b).unwrap()
 }
 
 fn solve_part_2(data: &[bool; KEYSPACE]) -> usize {
@@ -31,16 +16,162 @@ fn solve_part_2(data: &[bool; KEYSPACE]) -> usize {
         // first part are all false
         .skip_while(|(_, b)| !**b)
         // find first false
-        .skip_while(|(_, b)| **b)
-        .next()
+        .find(|(_, b)| !**b)
         .unwrap()
         .0
 }
 
 const KEYSPACE: usize = 2 << (10 - 1);
+
+const USAGE: &str = "usage: day5 [encode <id>... | decode <pass>... | report [--map] [--plane-rows <count>]] \
+    [--rows <bits>] [--columns <bits>] [--alphabet <row lower><row upper><column lower><column upper>]";
+
 fn main() {
-    let (input_data, time_read) = time(|| read_stdin());
-    let (data, time_parse) = time(|| parse_input(split(input_data)));
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    let (command, rest) = match args.split_first() {
+        None => return run_challenge(),
+        Some((&command, rest)) => (command, rest),
+    };
+    let flags = rest.iter().position(|a| a.starts_with("--"));
+    let (values, flags) = rest.split_at(flags.unwrap_or(rest.len()));
+    match command {
+        "report" if values.is_empty() => run_report(flags),
+        "encode" | "decode" if !values.is_empty() => run_passes(command, values, flags),
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn usage_error(error: String) -> ! {
+    eprintln!("{}\n{}", error, USAGE);
+    std::process::exit(1);
+}
+
+fn run_report(flags: &[&str]) {
+    let (map, rows, layout) = parse_report_flags(flags)
+        .and_then(|(map, rows, flags)| Ok((map, rows, parse_layout(&flags)?)))
+        .unwrap_or_else(|e| usage_error(e));
+    let rows = rows.unwrap_or_else(|| layout.row_count());
+    let report = SeatReport::new(layout, rows, &read_stdin());
+    print!("{}", report.to_text());
+    if map {
+        match report.map() {
+            Ok(map) => print!("{}", map),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Encodes seat IDs into passes or decodes passes into seats, depending on `command`.
+fn run_passes(command: &str, values: &[&str], flags: &[&str]) {
+    let layout = parse_layout(flags).unwrap_or_else(|e| usage_error(e));
+    let mut failed = false;
+    for value in values {
+        let result = match command {
+            "encode" => value
+                .parse()
+                .ok()
+                .and_then(|id| layout.encode(id))
+                .ok_or_else(|| format!("{}: not a seat ID in this plane", value)),
+            _ => layout
+                .decode(value)
+                .map_err(|e| format!("{}: {}", value, e))
+                .map(|id| {
+                    let (row, column) = layout.position(id);
+                    format!("{} (row {}, column {})", id, row, column)
+                }),
+        };
+        match result {
+            Ok(line) => println!("{}", line),
+            Err(error) => {
+                eprintln!("{}", error);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Takes `--map` and `--plane-rows` out of the flags of `report`, leaving the layout flags.
+fn parse_report_flags<'a>(
+    flags: &[&'a str],
+) -> Result<(bool, Option<usize>, Vec<&'a str>), String> {
+    let mut map = false;
+    let mut rows = None;
+    let mut rest = vec![];
+    let mut iter = flags.iter();
+    while let Some(&flag) = iter.next() {
+        match flag {
+            "--map" => map = true,
+            "--plane-rows" => {
+                let value = iter.next().ok_or("--plane-rows needs a value")?;
+                let count = value
+                    .parse()
+                    .map_err(|_| format!("invalid row count {}", value))?;
+                rows = Some(count);
+            }
+            _ => {
+                rest.push(flag);
+                rest.extend(iter.next());
+            }
+        }
+    }
+    Ok((map, rows, rest))
+}
+
+/// Parses `--rows`, `--columns` and `--alphabet`, which default to the puzzle's layout.
+fn parse_layout(flags: &[&str]) -> Result<Layout, String> {
+    let mut layout = STANDARD;
+    for pair in flags.chunks(2) {
+        let (flag, value) = match pair {
+            [flag, value] => (*flag, *value),
+            [flag] => return Err(format!("{} needs a value", flag)),
+            _ => unreachable!(),
+        };
+        let bits = || {
+            value
+                .parse()
+                .map_err(|_| format!("invalid bit count {}", value))
+        };
+        match flag {
+            "--rows" => layout.row_bits = bits()?,
+            "--columns" => layout.column_bits = bits()?,
+            "--alphabet" => match value.chars().collect::<Vec<_>>().as_slice() {
+                &[row_lower, row_upper, column_lower, column_upper] => {
+                    layout.rows = Alphabet {
+                        lower: row_lower,
+                        upper: row_upper,
+                    };
+                    layout.columns = Alphabet {
+                        lower: column_lower,
+                        upper: column_upper,
+                    };
+                }
+                _ => return Err(format!("alphabet {} should have 4 characters", value)),
+            },
+            _ => return Err(format!("unknown flag {}", flag)),
+        }
+    }
+    Layout::new(
+        layout.row_bits,
+        layout.column_bits,
+        layout.rows,
+        layout.columns,
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn run_challenge() {
+    let (input_data, time_read) = time(read_stdin);
+    let (data, time_parse) = time(|| parse_input(&input_data));
     let (solution_1, time_1) = time(|| solve_part_1(&data));
     let (solution_2, time_2) = time(|| solve_part_2(&data));
 
@@ -52,17 +183,13 @@ fn main() {
     println!("Time to solve 2: {:?}", time_2);
 }
 
-fn split(str: String) -> Vec<[u8; 10]> {
-    str.lines()
-        .map(|line| line.as_bytes().try_into().unwrap())
-        .collect::<Vec<[u8; 10]>>()
-}
-
-fn parse_input(input_data: Vec<[u8; 10]>) -> [bool; KEYSPACE] {
+fn parse_input(input_data: &str) -> [bool; KEYSPACE] {
     let mut data = [false; KEYSPACE];
-    for f in input_data {
-        let decoded = decode_partition_10(&f);
-        data[decoded] = true;
+    for (idx, line) in input_data.lines().enumerate() {
+        match STANDARD.decode(line) {
+            Ok(decoded) => data[decoded] = true,
+            Err(error) => panic!("line {}: {}", idx + 1, error),
+        }
     }
     data
 }
@@ -73,9 +200,19 @@ mod tests {
 
     #[test]
     fn test_binary_partition() {
-        assert_eq!(357, decode_partition_10(b"FBFBBFFRLR"));
-        assert_eq!(567, decode_partition_10(b"BFFFBBFRRR"));
-        assert_eq!(119, decode_partition_10(b"FFFBBBFRRR"));
-        assert_eq!(820, decode_partition_10(b"BBFFBBFRLL"));
+        assert_eq!(Ok(357), STANDARD.decode("FBFBBFFRLR"));
+        assert_eq!(Ok(567), STANDARD.decode("BFFFBBFRRR"));
+        assert_eq!(Ok(119), STANDARD.decode("FFFBBBFRRR"));
+        assert_eq!(Ok(820), STANDARD.decode("BBFFBBFRLL"));
+    }
+
+    #[test]
+    fn test_parse_layout() {
+        assert_eq!(Ok(STANDARD), parse_layout(&[]));
+        let layout = parse_layout(&["--rows", "2", "--alphabet", "abcd"]).unwrap();
+        assert_eq!(Some("aaddc".to_owned()), layout.encode(0b0110));
+        assert!(parse_layout(&["--rows"]).is_err());
+        assert!(parse_layout(&["--alphabet", "abc"]).is_err());
+        assert!(parse_layout(&["--columns", "70"]).is_err());
     }
 }
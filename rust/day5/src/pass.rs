@@ -0,0 +1,306 @@
+use std::fmt;
+
+/// The two characters that pick the lower or upper half of a range.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Alphabet {
+    pub lower: char,
+    pub upper: char,
+}
+
+/// How a boarding pass is laid out: first the row bits, then the column bits, most significant
+/// first. The seat ID is `row << column_bits | column`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Layout {
+    pub row_bits: u32,
+    pub column_bits: u32,
+    pub rows: Alphabet,
+    pub columns: Alphabet,
+}
+
+/// The layout from the puzzle: 128 rows picked with `F`/`B` and 8 columns picked with `L`/`R`.
+pub const STANDARD: Layout = Layout {
+    row_bits: 7,
+    column_bits: 3,
+    rows: Alphabet {
+        lower: 'F',
+        upper: 'B',
+    },
+    columns: Alphabet {
+        lower: 'L',
+        upper: 'R',
+    },
+};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum LayoutError {
+    /// Seat IDs wouldn't fit in a `usize`.
+    TooWide(u32),
+    /// Both halves use the same character.
+    Ambiguous(char),
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::TooWide(bits) => write!(f, "{} bits don't fit in a seat ID", bits),
+            LayoutError::Ambiguous(c) => write!(f, "'{}' is used for both halves", c),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DecodeError {
+    WrongLength {
+        expected: usize,
+        found: usize,
+    },
+    /// A character that doesn't belong at this 0-based position.
+    InvalidChar {
+        position: usize,
+        found: char,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::WrongLength { expected, found } => {
+                write!(f, "expected {} characters but found {}", expected, found)
+            }
+            DecodeError::InvalidChar { position, found } => {
+                write!(f, "unexpected '{}' at position {}", found, position + 1)
+            }
+        }
+    }
+}
+
+impl Layout {
+    pub fn new(
+        row_bits: u32,
+        column_bits: u32,
+        rows: Alphabet,
+        columns: Alphabet,
+    ) -> Result<Self, LayoutError> {
+        let bits = row_bits.saturating_add(column_bits);
+        if bits >= usize::BITS {
+            return Err(LayoutError::TooWide(bits));
+        }
+        for alphabet in &[rows, columns] {
+            if alphabet.lower == alphabet.upper {
+                return Err(LayoutError::Ambiguous(alphabet.lower));
+            }
+        }
+
+        Ok(Self {
+            row_bits,
+            column_bits,
+            rows,
+            columns,
+        })
+    }
+
+    /// The number of characters in a pass.
+    pub fn len(&self) -> usize {
+        (self.row_bits + self.column_bits) as usize
+    }
+
+    /// The number of seats, which is one more than the highest seat ID.
+    pub fn seat_count(&self) -> usize {
+        1 << self.len()
+    }
+
+    pub fn row_count(&self) -> usize {
+        1 << self.row_bits
+    }
+
+    pub fn column_count(&self) -> usize {
+        1 << self.column_bits
+    }
+
+    pub fn seat_id(&self, row: usize, column: usize) -> usize {
+        row << self.column_bits | column
+    }
+
+    /// Splits a seat ID into its row and column.
+    pub fn position(&self, id: usize) -> (usize, usize) {
+        (id >> self.column_bits, id & (self.column_count() - 1))
+    }
+
+    fn alphabet(&self, position: usize) -> Alphabet {
+        if position < self.row_bits as usize {
+            self.rows
+        } else {
+            self.columns
+        }
+    }
+
+    pub fn decode(&self, pass: &str) -> Result<usize, DecodeError> {
+        let found = pass.chars().count();
+        if found != self.len() {
+            return Err(DecodeError::WrongLength {
+                expected: self.len(),
+                found,
+            });
+        }
+
+        let mut id = 0;
+        for (position, c) in pass.chars().enumerate() {
+            let alphabet = self.alphabet(position);
+            let bit = match c {
+                c if c == alphabet.lower => 0,
+                c if c == alphabet.upper => 1,
+                found => return Err(DecodeError::InvalidChar { position, found }),
+            };
+            id = id << 1 | bit;
+        }
+        Ok(id)
+    }
+
+    /// Writes the pass for a seat ID, or `None` if the plane doesn't have that seat.
+    pub fn encode(&self, id: usize) -> Option<String> {
+        if id >= self.seat_count() {
+            return None;
+        }
+
+        let pass = (0..self.len())
+            .map(|position| {
+                let alphabet = self.alphabet(position);
+                match id >> (self.len() - 1 - position) & 1 {
+                    0 => alphabet.lower,
+                    _ => alphabet.upper,
+                }
+            })
+            .collect();
+        Some(pass)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layouts() -> Vec<Layout> {
+        let digits = Alphabet {
+            lower: '0',
+            upper: '1',
+        };
+        let accented = Alphabet {
+            lower: 'é',
+            upper: 'ß',
+        };
+        vec![
+            STANDARD,
+            Layout::new(0, 0, digits, digits).unwrap(),
+            Layout::new(4, 0, accented, digits).unwrap(),
+            Layout::new(0, 5, digits, accented).unwrap(),
+            Layout::new(6, 6, digits, STANDARD.columns).unwrap(),
+            // the same characters for rows and columns, where only the position tells them apart
+            Layout::new(3, 4, STANDARD.rows, STANDARD.rows).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn test_standard() {
+        assert_eq!(Ok(357), STANDARD.decode("FBFBBFFRLR"));
+        assert_eq!((44, 5), STANDARD.position(357));
+        assert_eq!(Some("BBFFBBFRLL".to_owned()), STANDARD.encode(820));
+        assert_eq!(Some("BBBBBBBRRR".to_owned()), STANDARD.encode(1023));
+        assert_eq!(None, STANDARD.encode(1024));
+
+        assert_eq!(
+            Err(DecodeError::WrongLength {
+                expected: 10,
+                found: 9
+            }),
+            STANDARD.decode("FBFBBFFRL")
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidChar {
+                position: 7,
+                found: 'F'
+            }),
+            STANDARD.decode("FBFBBFFFLR")
+        );
+    }
+
+    #[test]
+    fn test_layout_errors() {
+        let columns = STANDARD.columns;
+        assert_eq!(
+            Err(LayoutError::TooWide(64)),
+            Layout::new(60, 4, STANDARD.rows, columns)
+        );
+        let same = Alphabet {
+            lower: 'X',
+            upper: 'X',
+        };
+        assert_eq!(
+            Err(LayoutError::Ambiguous('X')),
+            Layout::new(7, 3, STANDARD.rows, same)
+        );
+    }
+
+    /// Every seat ID survives encoding and decoding.
+    #[test]
+    fn test_decode_inverts_encode() {
+        for layout in layouts() {
+            for id in 0..layout.seat_count() {
+                let pass = layout.encode(id).unwrap();
+                assert_eq!(Ok(id), layout.decode(&pass), "{:?} {}", layout, pass);
+
+                let (row, column) = layout.position(id);
+                assert!(row < layout.row_count() && column < layout.column_count());
+                assert_eq!(id, layout.seat_id(row, column));
+            }
+            assert_eq!(None, layout.encode(layout.seat_count()));
+        }
+    }
+
+    /// Every valid pass survives decoding and encoding, and every other one is rejected. Checks
+    /// all strings of the right length over the characters of the layout plus one that isn't.
+    #[test]
+    fn test_encode_inverts_decode() {
+        for layout in layouts() {
+            let mut chars = vec![
+                layout.rows.lower,
+                layout.rows.upper,
+                layout.columns.lower,
+                layout.columns.upper,
+                '?',
+            ];
+            chars.sort_unstable();
+            chars.dedup();
+
+            // all of them if there are few enough, otherwise a fixed pseudo-random sample
+            let combinations = (chars.len() as u64).pow(layout.len() as u32);
+            let mut state = 0x2545_f491_4f6c_dd1d_u64;
+            let samples = (0..combinations.min(100_000)).map(|n| {
+                if combinations <= 100_000 {
+                    return n as usize;
+                }
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state % combinations) as usize
+            });
+            for mut n in samples {
+                let pass = (0..layout.len())
+                    .map(|_| {
+                        let c = chars[n % chars.len()];
+                        n /= chars.len();
+                        c
+                    })
+                    .collect::<String>();
+
+                match layout.decode(&pass) {
+                    Ok(id) => assert_eq!(Some(pass), layout.encode(id)),
+                    Err(DecodeError::InvalidChar { position, found }) => {
+                        let alphabet = layout.alphabet(position);
+                        assert!(found != alphabet.lower && found != alphabet.upper);
+                    }
+                    Err(error) => panic!("{}", error),
+                }
+            }
+        }
+    }
+}
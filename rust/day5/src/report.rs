@@ -0,0 +1,372 @@
+use std::{collections::BTreeMap, fmt, ops::Range};
+
+use crate::pass::{DecodeError, Layout};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Duplicate {
+    pub id: usize,
+    /// Every 1-based line with this seat.
+    pub lines: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Problem {
+    Invalid(DecodeError),
+    /// The row is past the last row of the plane.
+    PastLastRow(usize),
+}
+
+/// A pass that doesn't belong to a seat on the plane.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Outside {
+    /// 1-based.
+    pub line: usize,
+    pub pass: String,
+    pub problem: Problem,
+}
+
+/// The most columns [`SeatReport::map`] draws on a line.
+pub const MAX_MAP_COLUMNS: usize = 256;
+/// The most rows [`SeatReport::map`] draws.
+pub const MAX_MAP_ROWS: usize = 4096;
+
+/// Why a map is too big to draw.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum MapError {
+    /// The plane has this many columns.
+    TooWide(usize),
+    /// This many rows lie between the first and the last taken seat.
+    TooTall(usize),
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::TooWide(columns) => write!(
+                f,
+                "can't draw {} columns, at most {} fit",
+                columns, MAX_MAP_COLUMNS
+            ),
+            MapError::TooTall(rows) => {
+                write!(f, "can't draw {} rows, at most {} fit", rows, MAX_MAP_ROWS)
+            }
+        }
+    }
+}
+
+/// Which seats are taken, and everything that looks off about the passes.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SeatReport {
+    pub layout: Layout,
+    /// How many rows the plane actually has, which can be fewer than the layout allows.
+    pub rows: usize,
+    /// The 1-based lines of the passes for every taken seat, by seat ID. Only taken seats are
+    /// stored, so planes of any size fit.
+    pub seats: BTreeMap<usize, Vec<usize>>,
+    pub duplicates: Vec<Duplicate>,
+    pub outside: Vec<Outside>,
+}
+
+impl SeatReport {
+    /// Reads one pass per line, skipping empty lines.
+    pub fn new(layout: Layout, rows: usize, passes: &str) -> Self {
+        let rows = rows.min(layout.row_count());
+        let seat_count = layout.seat_id(rows, 0);
+        let mut seats = BTreeMap::<usize, Vec<usize>>::new();
+        let mut outside = vec![];
+        for (idx, pass) in passes.lines().enumerate() {
+            let pass = pass.trim();
+            if pass.is_empty() {
+                continue;
+            }
+
+            let problem = match layout.decode(pass) {
+                Ok(id) if id < seat_count => {
+                    seats.entry(id).or_default().push(idx + 1);
+                    continue;
+                }
+                Ok(id) => Problem::PastLastRow(layout.position(id).0),
+                Err(error) => Problem::Invalid(error),
+            };
+            outside.push(Outside {
+                line: idx + 1,
+                pass: pass.to_owned(),
+                problem,
+            });
+        }
+
+        let duplicates = seats
+            .iter()
+            .filter(|(_, lines)| lines.len() > 1)
+            .map(|(&id, lines)| Duplicate {
+                id,
+                lines: lines.clone(),
+            })
+            .collect();
+        Self {
+            layout,
+            rows,
+            seats,
+            duplicates,
+            outside,
+        }
+    }
+
+    /// The seat IDs of the first and last taken seats.
+    fn taken_range(&self) -> Option<(usize, usize)> {
+        let (&first, _) = self.seats.iter().next()?;
+        let (&last, _) = self.seats.iter().next_back()?;
+        Some((first, last))
+    }
+
+    /// Every run of empty seats between the first and the last taken seat, as seat IDs. The seats
+    /// before and after those don't exist on the plane.
+    pub fn missing(&self) -> Vec<Range<usize>> {
+        let ids = self.seats.keys().copied().collect::<Vec<_>>();
+        ids.windows(2)
+            .map(|pair| pair[0] + 1..pair[1])
+            .filter(|run| !run.is_empty())
+            .collect()
+    }
+
+    /// How many passes there are for the seat.
+    fn count(&self, id: usize) -> usize {
+        self.seats.get(&id).map_or(0, Vec::len)
+    }
+
+    fn seat(&self, id: usize) -> String {
+        let (row, column) = self.layout.position(id);
+        format!("seat {} (row {}, column {})", id, row, column)
+    }
+
+    pub fn to_text(&self) -> String {
+        let missing = self.missing();
+        let missing_count = missing.iter().map(|run| run.len()).sum::<usize>();
+        let passes = self.seats.values().map(Vec::len).sum::<usize>() + self.outside.len();
+        let mut string = format!(
+            "{} passes, {} missing seats, {} duplicated seats, {} outside the plane\n",
+            passes,
+            missing_count,
+            self.duplicates.len(),
+            self.outside.len()
+        );
+
+        if !missing.is_empty() {
+            string += "missing:\n";
+            for run in missing {
+                string += &match run.len() {
+                    1 => format!("  {}\n", self.seat(run.start)),
+                    _ => format!("  {} to {}\n", self.seat(run.start), self.seat(run.end - 1)),
+                };
+            }
+        }
+        if !self.duplicates.is_empty() {
+            string += "duplicated:\n";
+            for duplicate in &self.duplicates {
+                let lines = duplicate
+                    .lines
+                    .iter()
+                    .map(|l| l.to_string())
+                    .collect::<Vec<_>>();
+                string += &format!(
+                    "  {} on lines {}\n",
+                    self.seat(duplicate.id),
+                    lines.join(", ")
+                );
+            }
+        }
+        if !self.outside.is_empty() {
+            string += "outside the plane:\n";
+            for outside in &self.outside {
+                let problem = match &outside.problem {
+                    Problem::Invalid(error) => error.to_string(),
+                    Problem::PastLastRow(row) => {
+                        format!("row {} is past the plane's {} rows", row, self.rows)
+                    }
+                };
+                string += &format!("  line {}: {}: {}\n", outside.line, outside.pass, problem);
+            }
+        }
+        string
+    }
+
+    /// Draws the rows from the first to the last taken seat with their numbers. `#` is a taken
+    /// seat, `2` to `9` a seat with that many passes (`+` for more), `.` a missing seat and a space
+    /// a seat before the first or after the last taken one. Fails for maps larger than
+    /// [`MAX_MAP_COLUMNS`] by [`MAX_MAP_ROWS`].
+    pub fn map(&self) -> Result<String, MapError> {
+        let range = self.taken_range();
+        let rows = range
+            .map(|(first, last)| (self.layout.position(first).0, self.layout.position(last).0));
+        if self.layout.column_count() > MAX_MAP_COLUMNS {
+            return Err(MapError::TooWide(self.layout.column_count()));
+        }
+        if let Some((first, last)) = rows {
+            if last - first >= MAX_MAP_ROWS {
+                return Err(MapError::TooTall(last - first + 1));
+            }
+        }
+
+        let width = rows.map_or(1, |(_, last)| last.to_string().len());
+        let columns = (0..self.layout.column_count())
+            .map(|c| std::char::from_digit((c % 10) as u32, 10).unwrap())
+            .collect::<String>();
+        let mut string = format!("{:>width$} {}\n", "", columns, width = width);
+
+        for row in rows.into_iter().flat_map(|(first, last)| first..=last) {
+            let seats = (0..self.layout.column_count())
+                .map(|column| {
+                    let id = self.layout.seat_id(row, column);
+                    match self.count(id) {
+                        0 if range.is_some_and(|(first, last)| first < id && id < last) => '.',
+                        0 => ' ',
+                        1 => '#',
+                        count @ 2..=9 => std::char::from_digit(count as u32, 10).unwrap(),
+                        _ => '+',
+                    }
+                })
+                .collect::<String>();
+            string += format!("{:>width$} {}", row, seats, width = width).trim_end();
+            string += "\n";
+        }
+        Ok(string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pass::{Alphabet, STANDARD};
+
+    const PASSES: &str = "FBFBBFFRLR\n\
+        FBFBBFFRRL\n\
+        FBFBBFBLLR\n\
+        \n\
+        FBFBBFFRLR\n\
+        FBFBBFFRL\n\
+        FBFBBFFFLR\n\
+        BBBBBBBRRR\n";
+
+    #[test]
+    fn test_report() {
+        let report = SeatReport::new(STANDARD, 100, PASSES);
+
+        assert_eq!(vec![359..361], report.missing());
+        assert_eq!(
+            vec![Duplicate {
+                id: 357,
+                lines: vec![1, 5],
+            }],
+            report.duplicates
+        );
+        assert_eq!(
+            vec![6, 7, 8],
+            report.outside.iter().map(|o| o.line).collect::<Vec<_>>()
+        );
+        assert_eq!(Problem::PastLastRow(127), report.outside[2].problem);
+
+        let text = report.to_text();
+        assert!(text.starts_with(
+            "7 passes, 2 missing seats, 1 duplicated seats, 3 outside the plane\n\
+            missing:\n  seat 359 (row 44, column 7) to seat 360 (row 45, column 0)\n"
+        ));
+        assert!(text.contains(
+            "duplicated:\n  seat 357 (row 44, column 5) on lines 1, 5\n\
+            outside the plane:\n  line 6: FBFBBFFRL: expected 10 characters but found 9\n\
+            \x20 line 7: FBFBBFFFLR: unexpected 'F' at position 8\n\
+            \x20 line 8: BBBBBBBRRR: row 127 is past the plane's 100 rows\n"
+        ));
+    }
+
+    #[test]
+    fn test_map() {
+        let layout = Layout::new(
+            2,
+            2,
+            Alphabet {
+                lower: 'F',
+                upper: 'B',
+            },
+            STANDARD.columns,
+        )
+        .unwrap();
+        let report = SeatReport::new(layout, 4, "FBLR\nFBRL\nBFRR\nBFRR\nBFRR\nBBLL\n");
+
+        assert_eq!(
+            Ok("  0123\n\
+            1  ##.\n\
+            2 ...3\n\
+            3 #\n"
+                .to_owned()),
+            report.map()
+        );
+        assert_eq!(vec![7..11], report.missing());
+        assert_eq!(
+            Ok("  0123\n".to_owned()),
+            SeatReport::new(layout, 4, "").map()
+        );
+    }
+
+    #[test]
+    fn test_wide_layout() {
+        let layout = Layout::new(60, 3, STANDARD.rows, STANDARD.columns).unwrap();
+        let first = layout.seat_id(1 << 50, 6);
+        let passes = [first, first + 1, first + 3, first + 3]
+            .iter()
+            .map(|&id| layout.encode(id).unwrap() + "\n")
+            .collect::<String>()
+            + "FBFBBFFRLR\n";
+        let report = SeatReport::new(layout, layout.row_count(), &passes);
+
+        assert_eq!(vec![first + 2..first + 3], report.missing());
+        assert_eq!(
+            vec![first + 3],
+            report.duplicates.iter().map(|d| d.id).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![5],
+            report.outside.iter().map(|o| o.line).collect::<Vec<_>>()
+        );
+        assert!(report
+            .to_text()
+            .starts_with("5 passes, 1 missing seats, 1 duplicated seats"));
+        assert_eq!(
+            Ok("                 01234567\n\
+            1125899906842624       ##\n\
+            1125899906842625 .2\n"
+                .to_owned()),
+            report.map()
+        );
+    }
+
+    #[test]
+    fn test_far_apart() {
+        let layout = Layout::new(40, 3, STANDARD.rows, STANDARD.columns).unwrap();
+        let passes = format!(
+            "{}\n{}\n{}\n",
+            layout.encode(0).unwrap(),
+            layout.encode(8_000_000_000_000).unwrap(),
+            layout.encode(8_000_000_000_002).unwrap()
+        );
+        let report = SeatReport::new(layout, layout.row_count(), &passes);
+
+        assert_eq!(
+            vec![1..8_000_000_000_000, 8_000_000_000_001..8_000_000_000_002],
+            report.missing()
+        );
+        assert!(report.to_text().starts_with(
+            "3 passes, 8000000000000 missing seats, 0 duplicated seats, 0 outside the plane\n\
+            missing:\n  seat 1 (row 0, column 1) to seat 7999999999999 (row 999999999999, column 7)\n\
+            \x20 seat 8000000000001 (row 1000000000000, column 1)\n"
+        ));
+        assert_eq!(Err(MapError::TooTall(1_000_000_000_001)), report.map());
+
+        let wide = Layout::new(1, 40, STANDARD.rows, STANDARD.columns).unwrap();
+        let report = SeatReport::new(wide, 2, &(wide.encode(5).unwrap() + "\n"));
+        assert_eq!(Err(MapError::TooWide(1 << 40)), report.map());
+        assert_eq!(
+            "can't draw 1099511627776 columns, at most 256 fit",
+            report.map().unwrap_err().to_string()
+        );
+    }
+}
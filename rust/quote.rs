@@ -0,0 +1,33 @@
+// Shared between days, include with:
+// #[path = "../../quote.rs"]
+// mod quote;
+
+/// Quotes a string for use in JSON, which DOT accepts as well.
+pub fn quote(string: &str) -> String {
+    let mut quoted = String::with_capacity(string.len() + 2);
+    quoted.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => quoted += "\\\"",
+            '\\' => quoted += "\\\\",
+            '\n' => quoted += "\\n",
+            c if c.is_control() => quoted += &format!("\\u{:04x}", c as u32),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!("\"shiny gold\"", quote("shiny gold"));
+        assert_eq!("\"x\\\"é\\n\"", quote("x\"é\n"));
+        assert_eq!("\"a\\\\b\\u0009\\u007f\"", quote("a\\b\t\x7f"));
+        assert_eq!("\"\"", quote(""));
+    }
+}
@@ -0,0 +1,85 @@
+// Shared between days, include with:
+// #[path = "../../scc.rs"]
+// mod scc;
+
+/// Finds the strongly connected components of a graph given as the successors of every node,
+/// using Kosaraju's algorithm. Works without recursion so long chains can't overflow the stack.
+/// Every component is sorted, and components come in topological order.
+pub fn strongly_connected(adjacency: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let count = adjacency.len();
+
+    let mut visited = vec![false; count];
+    let mut order = Vec::with_capacity(count);
+    for root in 0..count {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        let mut stack = vec![(root, 0)];
+        while let Some(top) = stack.last_mut() {
+            let (node, child) = *top;
+            top.1 += 1;
+            match adjacency[node].get(child) {
+                Some(&next) if !visited[next] => {
+                    visited[next] = true;
+                    stack.push((next, 0));
+                }
+                Some(_) => (),
+                None => {
+                    order.push(node);
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    let mut reverse = vec![vec![]; count];
+    for (from, tos) in adjacency.iter().enumerate() {
+        for &to in tos {
+            reverse[to].push(from);
+        }
+    }
+
+    let mut assigned = vec![false; count];
+    let mut components = vec![];
+    for &root in order.iter().rev() {
+        if assigned[root] {
+            continue;
+        }
+        assigned[root] = true;
+        let mut members = vec![];
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            members.push(node);
+            for &prev in &reverse[node] {
+                if !assigned[prev] {
+                    assigned[prev] = true;
+                    stack.push(prev);
+                }
+            }
+        }
+        members.sort_unstable();
+        components.push(members);
+    }
+
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strongly_connected() {
+        // 0 -> 1 -> 2 -> 0, 2 -> 3, 3 -> 3, 4 alone
+        let adjacency = vec![vec![1], vec![2], vec![0, 3], vec![3], vec![]];
+        let mut components = strongly_connected(&adjacency);
+        components.sort();
+        assert_eq!(vec![vec![0, 1, 2], vec![3], vec![4]], components);
+
+        let chain = (0..200_000).map(|i| vec![i + 1]).chain(Some(vec![0]));
+        let components = strongly_connected(&chain.collect::<Vec<_>>());
+        assert_eq!(1, components.len());
+        assert_eq!(200_001, components[0].len());
+    }
+}
@@ -0,0 +1,142 @@
+// Shared between days, include with:
+// #[path = "../../string_interner.rs"]
+// mod string_interner;
+
+use std::{collections::HashMap, hash::Hash, rc::Rc};
+
+/// An integer type that can be used for [`StringKey`]. Smaller widths make keys cheaper to store
+/// but limit how many strings an interner can hold.
+pub trait KeyWidth: Copy + Eq + Hash + Ord + std::fmt::Debug {
+    fn from_index(index: usize) -> Option<Self>;
+    fn index(self) -> usize;
+}
+
+macro_rules! impl_key_width {
+    ($($t:ty),*) => {
+        $(
+            impl KeyWidth for $t {
+                fn from_index(index: usize) -> Option<Self> {
+                    use std::convert::TryFrom;
+                    <$t>::try_from(index).ok()
+                }
+
+                fn index(self) -> usize {
+                    self as usize
+                }
+            }
+        )*
+    };
+}
+
+impl_key_width!(u8, u16, u32, usize);
+
+/// A handle to a string in a [`StringInterner`]. Keys can only be created by interning, so every
+/// key is valid for the interner that created it.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
+pub struct StringKey<W: KeyWidth = u32>(W);
+
+impl<W: KeyWidth> StringKey<W> {
+    /// The position of the string in the interner, useful for indexing into other collections.
+    pub fn as_usize(self) -> usize {
+        self.0.index()
+    }
+}
+
+/// Hands out a small key for every distinct string. Each string is stored once, shared between
+/// the lookup in both directions.
+pub struct StringInterner<W: KeyWidth = u32> {
+    map: HashMap<Rc<str>, StringKey<W>>,
+    vec: Vec<Rc<str>>,
+}
+
+impl<W: KeyWidth> Default for StringInterner<W> {
+    fn default() -> Self {
+        Self {
+            map: HashMap::new(),
+            vec: vec![],
+        }
+    }
+}
+
+impl<W: KeyWidth> StringInterner<W> {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the key for `string`, adding it if it is new. Returns `None` if there are no keys of
+    /// this width left.
+    pub fn intern(&mut self, string: &str) -> Option<StringKey<W>> {
+        if let Some(&key) = self.map.get(string) {
+            return Some(key);
+        }
+
+        let key = StringKey(W::from_index(self.vec.len())?);
+        let shared = Rc::<str>::from(string);
+        self.vec.push(shared.clone());
+        self.map.insert(shared, key);
+        Some(key)
+    }
+
+    pub fn try_get_key(&self, string: &str) -> Option<StringKey<W>> {
+        self.map.get(string).copied()
+    }
+
+    pub fn lookup(&self, key: StringKey<W>) -> &str {
+        &self.vec[key.as_usize()]
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
+
+    /// All keys in the order they were handed out.
+    pub fn keys(&self) -> impl Iterator<Item = StringKey<W>> {
+        // every index below the length was a valid key when it was interned
+        (0..self.vec.len()).map(|i| StringKey(W::from_index(i).unwrap()))
+    }
+
+    #[allow(dead_code)]
+    pub fn iter(&self) -> impl Iterator<Item = (StringKey<W>, &str)> {
+        self.keys().zip(self.vec.iter().map(|s| &**s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interning() {
+        let mut interner = StringInterner::<u32>::new();
+        let red = interner.intern("light red").unwrap();
+        let gold = interner.intern("shiny gold").unwrap();
+
+        assert_eq!(Some(red), interner.intern("light red"));
+        assert_ne!(red, gold);
+        assert_eq!(2, interner.len());
+        assert_eq!("shiny gold", interner.lookup(gold));
+        assert_eq!(Some(gold), interner.try_get_key("shiny gold"));
+        assert_eq!(None, interner.try_get_key("dark olive"));
+        assert_eq!(
+            vec![(red, "light red"), (gold, "shiny gold")],
+            interner.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_key_width() {
+        let mut interner = StringInterner::<u8>::new();
+        for i in 0..256 {
+            assert_eq!(i, interner.intern(&i.to_string()).unwrap().as_usize());
+        }
+        assert_eq!(None, interner.intern("256"));
+        assert!(interner.intern("255").is_some());
+    }
+}